@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::metrics::{HardwareMetrics, Metric};
+
+mod sinks;
+
+pub use sinks::SinkConfig;
+use sinks::AlertSink;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+// One rule from the config file. Exactly one of `above` and `below` is set:
+//
+//   [[alerts.rules]]
+//   name = "vram-full"
+//   metric = "vram_pct"       # any key accepted by --metrics
+//   above = 95
+//   for_secs = 10             # the condition has to hold this long to fire
+//   hysteresis = 2            # resolves once back under 93
+//   severity = "critical"     # info, warning (default) or critical
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    pub name: String,
+    pub metric: String,
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    #[serde(default)]
+    pub for_secs: f64,
+    // How far the value has to move back past the threshold before the alert
+    // resolves, so a value hovering around the threshold doesn't flap.
+    #[serde(default)]
+    pub hysteresis: f64,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    pub rules: Vec<AlertRule>,
+    // Where fired and resolved events go. Empty means the GUI banner only.
+    pub sinks: Vec<SinkConfig>,
+}
+
+impl AlertsConfig {
+    // Catches mistakes at startup rather than at the first breach.
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if self.rules[..i].iter().any(|r| r.name == rule.name) {
+                return Err(format!("alert rule '{}' is defined twice", rule.name));
+            }
+            if Metric::from_key(&rule.metric).is_none() {
+                return Err(format!("alert rule '{}': unknown metric '{}'", rule.name, rule.metric));
+            }
+            if rule.above.is_some() == rule.below.is_some() {
+                return Err(format!("alert rule '{}' needs exactly one of `above` and `below`", rule.name));
+            }
+            if rule.for_secs < 0.0 || rule.hysteresis < 0.0 {
+                return Err(format!("alert rule '{}': for_secs and hysteresis can't be negative", rule.name));
+            }
+        }
+        self.sinks.iter().try_for_each(SinkConfig::validate)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Fired,
+    Resolved,
+}
+
+// What the sinks get, once when a rule fires and once when it resolves.
+#[derive(Clone, Serialize)]
+pub struct AlertEvent {
+    pub rule: String,
+    pub state: AlertState,
+    pub severity: Severity,
+    pub metric: String,
+    pub value: f64,
+    pub threshold: f64,
+    // Seconds since the Unix epoch, from the sample that triggered the event.
+    pub timestamp: f64,
+    // e.g. "vram-full fired: vram_pct is 96.20 %, above 95"
+    pub message: String,
+}
+
+// The rules currently firing, shared between the polling thread and the GUI.
+pub type Banner = Arc<Mutex<Vec<AlertEvent>>>;
+
+enum RuleState {
+    Ok,
+    // Breached since `since`, but not for `for_secs` yet.
+    Pending { since: f64 },
+    Firing,
+}
+
+struct Rule {
+    config: AlertRule,
+    metric: Metric,
+    state: RuleState,
+}
+
+impl Rule {
+    fn threshold(&self) -> f64 {
+        self.config.above.or(self.config.below).unwrap_or(0.0)
+    }
+
+    fn breached(&self, value: f64) -> bool {
+        match (self.config.above, self.config.below) {
+            (Some(above), _) => value > above,
+            (_, Some(below)) => value < below,
+            _ => false,
+        }
+    }
+
+    fn cleared(&self, value: f64) -> bool {
+        match (self.config.above, self.config.below) {
+            (Some(above), _) => value <= above - self.config.hysteresis,
+            (_, Some(below)) => value >= below + self.config.hysteresis,
+            _ => true,
+        }
+    }
+
+    // Advances the state machine by one sample. Returns the transition, if any.
+    fn step(&mut self, value: f64, t: f64) -> Option<AlertState> {
+        let since = match self.state {
+            RuleState::Firing if self.cleared(value) => {
+                self.state = RuleState::Ok;
+                return Some(AlertState::Resolved);
+            }
+            RuleState::Firing => return None,
+            _ if !self.breached(value) => {
+                self.state = RuleState::Ok;
+                return None;
+            }
+            RuleState::Ok => t,
+            RuleState::Pending { since } => since,
+        };
+        if t - since >= self.config.for_secs {
+            self.state = RuleState::Firing;
+            Some(AlertState::Fired)
+        } else {
+            self.state = RuleState::Pending { since };
+            None
+        }
+    }
+
+    fn event(&self, state: AlertState, value: f64, t: f64) -> AlertEvent {
+        let verb = match state {
+            AlertState::Fired => "fired",
+            AlertState::Resolved => "resolved",
+        };
+        let direction = if self.config.above.is_some() { "above" } else { "below" };
+        AlertEvent {
+            rule: self.config.name.clone(),
+            state,
+            severity: self.config.severity,
+            metric: self.metric.key().to_string(),
+            value,
+            threshold: self.threshold(),
+            timestamp: t,
+            message: format!(
+                "{} {}: {} is {:.2} {}, {} {}",
+                self.config.name,
+                verb,
+                self.metric.key(),
+                value,
+                self.metric.unit(),
+                if state == AlertState::Fired { direction } else { "threshold" },
+                self.threshold()
+            ),
+        }
+    }
+}
+
+// Evaluates every rule against each new sample and hands the transitions to
+// the sinks. Time comes from the samples, so a stalled polling thread can't
+// make a rule fire early.
+pub struct Alerts {
+    rules: Vec<Rule>,
+    sinks: Vec<Box<dyn AlertSink>>,
+}
+
+impl Alerts {
+    // `banner` is None when there is no window to show it in (headless mode);
+    // a configured `gui` sink is then ignored and `stdout` prints to stderr.
+    // Expects a validated config.
+    pub fn new(config: &AlertsConfig, banner: Option<Banner>) -> Self {
+        let rules = config
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                Some(Rule { metric: Metric::from_key(&rule.metric)?, config: rule.clone(), state: RuleState::Ok })
+            })
+            .collect();
+        let default_sinks = [SinkConfig::Gui];
+        let sinks = if config.sinks.is_empty() { &default_sinks[..] } else { &config.sinks[..] };
+        Self { rules, sinks: sinks.iter().filter_map(|sink| sinks::open(sink, banner.as_ref())).collect() }
+    }
+
+    pub fn evaluate(&mut self, m: &HardwareMetrics) {
+        let t = m.timestamp_secs();
+        for rule in &mut self.rules {
+            // A missing source is neither a breach nor a recovery.
+            if !m.has_source(rule.metric.source_id()) {
+                if let RuleState::Pending { .. } = rule.state {
+                    rule.state = RuleState::Ok;
+                }
+                continue;
+            }
+            let value = rule.metric.value(m);
+            if let Some(state) = rule.step(value, t) {
+                let event = rule.event(state, value, t);
+                for sink in &mut self.sinks {
+                    sink.send(&event);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::{SourceHealth, SourceStatus};
+    use std::time::{Duration, SystemTime};
+
+    fn config(above: Option<f64>, below: Option<f64>, for_secs: f64, hysteresis: f64) -> AlertRule {
+        AlertRule { name: "test".to_string(), metric: "cpu".to_string(), above, below, for_secs, hysteresis, severity: Severity::Warning }
+    }
+
+    fn rule(above: Option<f64>, below: Option<f64>, for_secs: f64, hysteresis: f64) -> Rule {
+        Rule { config: config(above, below, for_secs, hysteresis), metric: Metric::CpuUsage, state: RuleState::Ok }
+    }
+
+    // Feeds (value, time) pairs and collects the transitions.
+    fn run(rule: &mut Rule, samples: &[(f64, f64)]) -> Vec<Option<AlertState>> {
+        samples.iter().map(|&(value, t)| rule.step(value, t)).collect()
+    }
+
+    const FIRED: Option<AlertState> = Some(AlertState::Fired);
+    const RESOLVED: Option<AlertState> = Some(AlertState::Resolved);
+
+    #[test]
+    fn fires_only_after_for_secs() {
+        let mut r = rule(Some(90.0), None, 5.0, 0.0);
+        assert_eq!(run(&mut r, &[(95.0, 0.0), (95.0, 4.9), (95.0, 5.0), (99.0, 6.0)]), [None, None, FIRED, None]);
+        // Without for_secs the first breach fires.
+        let mut r = rule(Some(90.0), None, 0.0, 0.0);
+        assert_eq!(run(&mut r, &[(90.0, 0.0), (90.5, 1.0)]), [None, FIRED]);
+    }
+
+    #[test]
+    fn dip_while_pending_restarts_the_timer() {
+        let mut r = rule(Some(90.0), None, 5.0, 0.0);
+        let transitions = run(&mut r, &[(95.0, 0.0), (80.0, 2.0), (95.0, 3.0), (95.0, 7.0), (95.0, 8.0)]);
+        assert_eq!(transitions, [None, None, None, None, FIRED]);
+    }
+
+    #[test]
+    fn stays_firing_inside_the_hysteresis_band() {
+        let mut r = rule(Some(90.0), None, 0.0, 5.0);
+        let transitions = run(&mut r, &[(95.0, 0.0), (89.0, 1.0), (85.1, 2.0), (91.0, 3.0), (85.0, 4.0), (91.0, 5.0)]);
+        assert_eq!(transitions, [FIRED, None, None, None, RESOLVED, FIRED]);
+    }
+
+    #[test]
+    fn below_rules_mirror_above() {
+        let mut r = rule(None, Some(10.0), 2.0, 2.0);
+        let transitions = run(&mut r, &[(10.0, 0.0), (5.0, 1.0), (4.0, 3.0), (11.0, 4.0), (12.0, 5.0)]);
+        assert_eq!(transitions, [None, None, FIRED, None, RESOLVED]);
+    }
+
+    #[test]
+    fn missing_source_resets_pending() {
+        let config = AlertsConfig { rules: vec![config(Some(90.0), None, 5.0, 0.0)], sinks: Vec::new() };
+        let banner = Banner::default();
+        let mut alerts = Alerts::new(&config, Some(banner.clone()));
+        let sample = |t: u64, source: bool| HardwareMetrics {
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(t),
+            cpu_usage: 95.0,
+            sources: if source {
+                vec![SourceStatus { id: "cpu".to_string(), description: String::new(), enabled: true, health: SourceHealth::Ok }]
+            } else {
+                Vec::new()
+            },
+            ..Default::default()
+        };
+
+        alerts.evaluate(&sample(100, true));
+        alerts.evaluate(&sample(103, false));
+        // Would have fired at 105 had the gap counted towards for_secs.
+        alerts.evaluate(&sample(105, true));
+        alerts.evaluate(&sample(109, true));
+        assert!(banner.lock().unwrap().is_empty());
+        alerts.evaluate(&sample(110, true));
+        let firing = banner.lock().unwrap();
+        assert_eq!(firing.len(), 1);
+        assert_eq!(firing[0].message, "test fired: cpu is 95.00 %, above 90");
+    }
+}
@@ -0,0 +1,335 @@
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+use super::{AlertEvent, AlertState, Banner};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// One entry of `[[alerts.sinks]]`, selected by its `type`:
+//
+//   [[alerts.sinks]]
+//   type = "webhook"
+//   url = "http://alertmanager.local:8080/hooks/hw-mon"
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SinkConfig {
+    // Banner across the top of the window while a rule is firing.
+    Gui,
+    // One line per event; on stderr in headless mode.
+    Stdout,
+    // Appends every event to `path` as a JSON line.
+    Log { path: PathBuf },
+    // POSTs every event as JSON. Plain http only; put a local relay in front
+    // of anything that needs TLS.
+    Webhook { url: String },
+    // Runs `command` through the shell with the event in HWMON_ALERT_*
+    // environment variables.
+    Command { command: String },
+}
+
+impl SinkConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SinkConfig::Webhook { url } => parse_http_url(url).map(|_| ()),
+            SinkConfig::Command { command } if command.trim().is_empty() => {
+                Err("alert command sink needs a command".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub trait AlertSink: Send {
+    // Must not block the polling thread for long; slow deliveries go through
+    // a Queued worker.
+    fn send(&mut self, event: &AlertEvent);
+}
+
+// Builds the sink for one config entry. None if it can't work here (a banner
+// without a window, a log file that can't be opened).
+pub fn open(config: &SinkConfig, banner: Option<&Banner>) -> Option<Box<dyn AlertSink>> {
+    Some(match config {
+        SinkConfig::Gui => Box::new(BannerSink { banner: banner?.clone() }),
+        // Headless mode prints the samples to stdout, JSON lines included.
+        SinkConfig::Stdout => Box::new(StdoutSink { to_stderr: banner.is_none() }),
+        SinkConfig::Log { path } => {
+            match File::options().create(true).append(true).open(path) {
+                Ok(file) => Box::new(LogSink { file }),
+                Err(e) => {
+                    eprintln!("alert log {}: {}", path.display(), e);
+                    return None;
+                }
+            }
+        }
+        SinkConfig::Webhook { url } => {
+            let (host, port, path) = parse_http_url(url).ok()?;
+            let sink = WebhookSink { host, port, path };
+            Box::new(Queued::spawn("alert-webhook", move |event| sink.deliver(event))?)
+        }
+        SinkConfig::Command { command } => {
+            let sink = CommandSink { command: command.clone() };
+            Box::new(Queued::spawn("alert-command", move |event| sink.deliver(event))?)
+        }
+    })
+}
+
+// Hands events to one worker thread per sink, which delivers them in order.
+// A slow endpoint delays its own later events instead of piling up threads.
+// The worker exits when the sink is dropped.
+struct Queued {
+    queue: Sender<AlertEvent>,
+}
+
+impl Queued {
+    fn spawn(name: &str, deliver: impl Fn(&AlertEvent) + Send + 'static) -> Option<Self> {
+        let (queue, events) = mpsc::channel::<AlertEvent>();
+        let worker = std::thread::Builder::new().name(name.to_string()).spawn(move || {
+            for event in events {
+                deliver(&event);
+            }
+        });
+        match worker {
+            Ok(_) => Some(Self { queue }),
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                None
+            }
+        }
+    }
+}
+
+impl AlertSink for Queued {
+    fn send(&mut self, event: &AlertEvent) {
+        let _ = self.queue.send(event.clone());
+    }
+}
+
+struct BannerSink {
+    banner: Banner,
+}
+
+impl AlertSink for BannerSink {
+    fn send(&mut self, event: &AlertEvent) {
+        let mut firing = self.banner.lock().unwrap();
+        firing.retain(|e| e.rule != event.rule);
+        if event.state == AlertState::Fired {
+            firing.push(event.clone());
+            // Worst first, so the banner leads with what matters.
+            firing.sort_by_key(|e| std::cmp::Reverse(e.severity));
+        }
+    }
+}
+
+struct StdoutSink {
+    // Set in headless mode, so alerts don't end up in the sample output.
+    to_stderr: bool,
+}
+
+impl AlertSink for StdoutSink {
+    fn send(&mut self, event: &AlertEvent) {
+        if self.to_stderr {
+            eprintln!("[{}] {}", event.severity.label(), event.message);
+        } else {
+            println!("[{}] {}", event.severity.label(), event.message);
+        }
+    }
+}
+
+struct LogSink {
+    file: File,
+}
+
+impl AlertSink for LogSink {
+    fn send(&mut self, event: &AlertEvent) {
+        let line = serde_json::to_string(event).unwrap_or_default();
+        if let Err(e) = writeln!(self.file, "{}", line) {
+            eprintln!("alert log: {}", e);
+        }
+    }
+}
+
+// "http://host[:port][/path]" -> (host, port, path). IPv6 hosts are written
+// in brackets, "http://[::1]:9000/hook", and returned without them.
+fn parse_http_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| format!("webhook url '{}' must start with http://", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let bad_port = || format!("webhook url '{}' has a bad port", url);
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((host, "")) => (host, None),
+            Some((host, port)) => (host, Some(port.strip_prefix(':').ok_or_else(bad_port)?)),
+            None => return Err(format!("webhook url '{}' has an unclosed '['", url)),
+        },
+        None => match authority.split_once(':') {
+            Some((_, port)) if port.contains(':') => {
+                return Err(format!("webhook url '{}' needs brackets around an IPv6 host", url));
+            }
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| bad_port())?,
+        None => 80,
+    };
+    if host.is_empty() {
+        return Err(format!("webhook url '{}' has no host", url));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+struct WebhookSink {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl WebhookSink {
+    // Tries each address the host resolves to, giving each WEBHOOK_TIMEOUT to
+    // connect; a blackholed host would otherwise hold the worker for the OS
+    // connect timeout, minutes on Linux.
+    fn connect(&self) -> std::io::Result<TcpStream> {
+        let mut last_error = None;
+        for addr in (self.host.as_str(), self.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, WEBHOOK_TIMEOUT) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "host has no addresses")))
+    }
+
+    // "host:port", with IPv6 hosts back in brackets.
+    fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    fn post(&self, body: &str) -> std::io::Result<String> {
+        let mut stream = self.connect()?;
+        stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+        stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.authority(),
+            body.len(),
+            body
+        )?;
+        let mut status_line = String::new();
+        BufReader::new(&stream).read_line(&mut status_line)?;
+        Ok(status_line.trim_end().to_string())
+    }
+
+    fn deliver(&self, event: &AlertEvent) {
+        let body = serde_json::to_string(event).unwrap_or_default();
+        match self.post(&body) {
+            Ok(status) if status.split_whitespace().nth(1).is_some_and(|code| code.starts_with('2')) => {}
+            Ok(status) => eprintln!("alert webhook {}{}: {}", self.authority(), self.path, status),
+            Err(e) => eprintln!("alert webhook {}{}: {}", self.authority(), self.path, e),
+        }
+    }
+}
+
+struct CommandSink {
+    command: String,
+}
+
+impl CommandSink {
+    fn deliver(&self, event: &AlertEvent) {
+        #[cfg(windows)]
+        let mut command = {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(&self.command);
+            command
+        };
+        #[cfg(not(windows))]
+        let mut command = {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&self.command);
+            command
+        };
+        command
+            .env("HWMON_ALERT_RULE", &event.rule)
+            .env("HWMON_ALERT_STATE", if event.state == AlertState::Fired { "fired" } else { "resolved" })
+            .env("HWMON_ALERT_SEVERITY", event.severity.label())
+            .env("HWMON_ALERT_METRIC", &event.metric)
+            .env("HWMON_ALERT_VALUE", format!("{:.2}", event.value))
+            .env("HWMON_ALERT_THRESHOLD", event.threshold.to_string())
+            .env("HWMON_ALERT_MESSAGE", &event.message);
+        match command.status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => eprintln!("alert command `{}`: {}", self.command, status),
+            Err(e) => eprintln!("alert command `{}`: {}", self.command, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::Severity;
+
+    #[test]
+    fn queued_sink_delivers_in_order_on_one_thread() {
+        let (delivered, received) = mpsc::channel();
+        let mut sink = Queued::spawn("alert-test", move |event| {
+            delivered.send((event.rule.clone(), std::thread::current().name().map(str::to_string))).unwrap();
+        })
+        .unwrap();
+        for rule in ["a", "b", "c"] {
+            let event = AlertEvent {
+                rule: rule.to_string(),
+                state: AlertState::Fired,
+                severity: Severity::Info,
+                metric: "cpu".to_string(),
+                value: 0.0,
+                threshold: 0.0,
+                timestamp: 0.0,
+                message: String::new(),
+            };
+            sink.send(&event);
+        }
+        let events: Vec<(String, Option<String>)> = received.iter().take(3).collect();
+        let rules: Vec<&str> = events.iter().map(|(rule, _)| rule.as_str()).collect();
+        assert_eq!(rules, ["a", "b", "c"]);
+        assert!(events.iter().all(|(_, thread)| thread.as_deref() == Some("alert-test")));
+    }
+
+    #[test]
+    fn parses_webhook_urls() {
+        let parse = parse_http_url;
+        assert_eq!(parse("http://alertmanager.local:8080/hooks/hw-mon"), Ok(("alertmanager.local".into(), 8080, "/hooks/hw-mon".into())));
+        assert_eq!(parse("http://10.0.0.5"), Ok(("10.0.0.5".into(), 80, "/".into())));
+        assert_eq!(parse("http://[::1]:9000/hook"), Ok(("::1".into(), 9000, "/hook".into())));
+        assert_eq!(parse("http://[fe80::1%eth0]/hook"), Ok(("fe80::1%eth0".into(), 80, "/hook".into())));
+        assert_eq!(parse("http://[::1]"), Ok(("::1".into(), 80, "/".into())));
+
+        assert!(parse("https://example.com/").is_err());
+        assert!(parse("http://:8080/").is_err());
+        assert!(parse("http://host:http/").is_err());
+        assert!(parse("http://[::1/hook").is_err());
+        assert!(parse("http://[::1]9000/").is_err());
+        assert_eq!(parse("http://::1/"), Err("webhook url 'http://::1/' needs brackets around an IPv6 host".into()));
+    }
+
+    #[test]
+    fn brackets_ipv6_hosts_in_the_host_header() {
+        let sink = |host: &str| WebhookSink { host: host.to_string(), port: 9000, path: "/".to_string() };
+        assert_eq!(sink("::1").authority(), "[::1]:9000");
+        assert_eq!(sink("localhost").authority(), "localhost:9000");
+    }
+}
@@ -0,0 +1,376 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::history::History;
+use crate::metrics::{HardwareMetrics, Metric};
+
+// How much history a verdict is based on. Long enough to ignore a single
+// spike, short enough to follow phase changes in a training loop.
+pub const ANALYSIS_WINDOW: Duration = Duration::from_secs(30);
+
+// Below this many samples in the window we don't claim anything.
+const MIN_SAMPLES: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    Unknown,
+    Idle,
+    DiskBound,
+    PcieBound,
+    CpuBound,
+    MemoryPressure,
+    // Working, but no single resource is saturated.
+    Balanced,
+}
+
+impl State {
+    pub fn label(self) -> &'static str {
+        match self {
+            State::Unknown => "Not enough data",
+            State::Idle => "Idle",
+            State::DiskBound => "Disk-bound",
+            State::PcieBound => "PCIe-bound",
+            State::CpuBound => "CPU-bound",
+            State::MemoryPressure => "Memory pressure",
+            State::Balanced => "No bottleneck",
+        }
+    }
+}
+
+// Percentages a resource has to exceed (or stay under, for idle) in a sample
+// for that sample to count towards a state.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    pub cpu_busy_pct: f64,
+    pub memory_used_pct: f64,
+    pub disk_busy_pct: f64,
+    pub pcie_busy_pct: f64,
+    // Per-direction link bandwidth PCIe utilisation is measured against when
+    // the GPUs don't report their negotiated link.
+    pub pcie_ceiling_bps: f64,
+    pub idle_cpu_pct: f64,
+    pub idle_disk_busy_pct: f64,
+    pub idle_pcie_pct: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            cpu_busy_pct: 85.0,
+            memory_used_pct: 90.0,
+            disk_busy_pct: 85.0,
+            pcie_busy_pct: 70.0,
+            // PCIe 3.0 x16: 8 GT/s * 16 lanes with 128b/130b encoding.
+            pcie_ceiling_bps: 15.75e9,
+            idle_cpu_pct: 10.0,
+            idle_disk_busy_pct: 5.0,
+            idle_pcie_pct: 2.0,
+        }
+    }
+}
+
+pub struct Verdict {
+    pub state: State,
+    // Share of the window in which the winning condition held, 0..1.
+    pub confidence: f32,
+    pub explanation: String,
+    // Every candidate with its score, highest first, for the detail view.
+    pub scores: Vec<(State, f32)>,
+}
+
+// The window's samples, one Vec per resource, all the same length because
+// History pushes every metric on every sample.
+struct Window {
+    seconds: f64,
+    cpu_pct: Vec<f64>,
+    memory_pct: Vec<f64>,
+    disk_busy_pct: Vec<f64>,
+    pcie_pct: Vec<f64>,
+    // Whether pcie_pct is per GPU against its own link, or the aggregate
+    // against the configured ceiling.
+    pcie_per_link: bool,
+    disk_read: Vec<f64>,
+    disk_write: Vec<f64>,
+    // Pressure stall "some" avg10, in %; only meaningful when `psi` is set.
+    psi: bool,
+    psi_cpu: Vec<f64>,
+    psi_memory: Vec<f64>,
+    psi_io: Vec<f64>,
+}
+
+impl Window {
+    fn collect(history: &History, latest: &HardwareMetrics, ceiling_bps: f64) -> Self {
+        let latest_time = history.latest_time().unwrap_or(0.0);
+        let since = latest_time - ANALYSIS_WINDOW.as_secs_f64();
+        let recent = |metric: Metric| -> Vec<f64> {
+            history.series(metric).filter(|p| p[0] >= since).map(|p| p[1]).collect()
+        };
+
+        let ram_total = latest.ram_total_gb as f64;
+        let memory_pct = recent(Metric::RamUsed)
+            .into_iter()
+            .map(|used| if ram_total > 0.0 { used / ram_total * 100.0 } else { 0.0 })
+            .collect();
+        // Each GPU has its own link and each direction its own lanes, so the
+        // busiest direction of the busiest link is what saturates. Without link
+        // information, fall back to the summed traffic against the configured ceiling.
+        let pcie_per_link = latest.gpus.iter().any(|g| g.pcie_util_pct().is_some());
+        let pcie_pct = if pcie_per_link {
+            recent(Metric::PcieLinkUtil)
+        } else {
+            recent(Metric::PcieTx)
+                .into_iter()
+                .zip(recent(Metric::PcieRx))
+                .map(|(tx, rx)| tx.max(rx) * 1024.0 * 1024.0 / ceiling_bps * 100.0)
+                .collect()
+        };
+
+        // Right after startup the window is only partly filled.
+        let first_time = history.series(Metric::CpuUsage).find(|p| p[0] >= since).map_or(latest_time, |p| p[0]);
+
+        Self {
+            seconds: latest_time - first_time,
+            cpu_pct: recent(Metric::CpuUsage),
+            memory_pct,
+            disk_busy_pct: recent(Metric::DiskBusy),
+            pcie_pct,
+            pcie_per_link,
+            disk_read: recent(Metric::DiskRead),
+            disk_write: recent(Metric::DiskWrite),
+            psi: latest.has_source("psi"),
+            psi_cpu: recent(Metric::PsiCpu),
+            psi_memory: recent(Metric::PsiMemory),
+            psi_io: recent(Metric::PsiIo),
+        }
+    }
+
+    // How much of the window tasks spent waiting on the resource, as a
+    // sentence to append to the verdict, or nothing without PSI.
+    fn stalled(&self, series: &[f64], waiting: &str) -> String {
+        if !self.psi {
+            return String::new();
+        }
+        format!(" Tasks were stalled {} {:.1}% of the time.", waiting, mean(series))
+    }
+
+    fn len(&self) -> usize {
+        self.cpu_pct.len()
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+// Share of samples above `threshold`.
+fn share_above(values: &[f64], threshold: f64) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().filter(|&&v| v > threshold).count() as f32 / values.len() as f32
+}
+
+// Classifies the last ANALYSIS_WINDOW of history. Each candidate state is
+// scored by how much of the window its condition held for; the best one wins
+// if it held for at least half of it.
+pub fn analyze(history: &History, latest: &HardwareMetrics, t: &Thresholds) -> Verdict {
+    let w = Window::collect(history, latest, t.pcie_ceiling_bps);
+    let n = w.len();
+    if n < MIN_SAMPLES {
+        return Verdict {
+            state: State::Unknown,
+            confidence: 0.0,
+            explanation: format!("Collecting samples ({} of {}).", n, MIN_SAMPLES),
+            scores: Vec::new(),
+        };
+    }
+
+    let idle = (0..n)
+        .filter(|&i| {
+            w.cpu_pct[i] < t.idle_cpu_pct && w.disk_busy_pct[i] < t.idle_disk_busy_pct && w.pcie_pct[i] < t.idle_pcie_pct
+        })
+        .count() as f32
+        / n as f32;
+
+    let mut scores = vec![
+        (State::DiskBound, share_above(&w.disk_busy_pct, t.disk_busy_pct)),
+        (State::PcieBound, share_above(&w.pcie_pct, t.pcie_busy_pct)),
+        (State::CpuBound, share_above(&w.cpu_pct, t.cpu_busy_pct)),
+        (State::MemoryPressure, share_above(&w.memory_pct, t.memory_used_pct)),
+        (State::Idle, idle),
+    ];
+    // Stable sort keeps the listed order as the tie-breaker: a saturated disk
+    // usually explains a busy CPU (iowait, decompression), not the reverse.
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let (state, confidence) = match scores[0] {
+        (state, score) if score >= 0.5 => (state, score),
+        (_, score) => (State::Balanced, 1.0 - score),
+    };
+
+    Verdict { state, confidence, explanation: explain(state, confidence, &w, t), scores }
+}
+
+fn explain(state: State, confidence: f32, w: &Window, t: &Thresholds) -> String {
+    let held = format!("{:.0}% of the last {:.0} s", confidence * 100.0, w.seconds.max(1.0));
+    match state {
+        State::DiskBound => format!(
+            "The busiest disk was over {:.0}% busy for {} (avg read {:.1} MB/s, write {:.1} MB/s) \
+             while CPU averaged {:.0}%. Data is not coming off storage fast enough to keep the GPU fed.{}",
+            t.disk_busy_pct,
+            held,
+            mean(&w.disk_read),
+            mean(&w.disk_write),
+            mean(&w.cpu_pct),
+            w.stalled(&w.psi_io, "on I/O")
+        ),
+        State::PcieBound => format!(
+            "GPU PCIe traffic was above {:.0}% of {} for {} (avg {:.0}%). \
+             Host-to-device copies are limited by the bus; pinned memory or fewer transfers may help.",
+            t.pcie_busy_pct,
+            if w.pcie_per_link {
+                "the busiest GPU's negotiated link".to_string()
+            } else {
+                format!("the {:.1} GB/s link", t.pcie_ceiling_bps / 1e9)
+            },
+            held,
+            mean(&w.pcie_pct)
+        ),
+        State::CpuBound => format!(
+            "CPU was above {:.0}% for {} (avg {:.0}%) while the busiest disk averaged {:.0}% busy. \
+             Host-side preprocessing is the limit, not storage or the bus.{}",
+            t.cpu_busy_pct,
+            held,
+            mean(&w.cpu_pct),
+            mean(&w.disk_busy_pct),
+            w.stalled(&w.psi_cpu, "waiting for a CPU")
+        ),
+        State::MemoryPressure => format!(
+            "RAM use was above {:.0}% for {} (avg {:.0}%). The page cache is being squeezed, \
+             so reads go back to disk and swapping may follow.{}",
+            t.memory_used_pct,
+            held,
+            mean(&w.memory_pct),
+            w.stalled(&w.psi_memory, "on memory reclaim")
+        ),
+        State::Idle => format!(
+            "CPU under {:.0}%, disks under {:.0}% busy and PCIe under {:.0}% of the link for {}.",
+            t.idle_cpu_pct, t.idle_disk_busy_pct, t.idle_pcie_pct, held
+        ),
+        State::Balanced => format!(
+            "No resource stayed above its threshold for most of the last {:.0} s \
+             (CPU avg {:.0}%, busiest disk avg {:.0}%, PCIe avg {:.0}% of link).{}",
+            w.seconds.max(1.0),
+            mean(&w.cpu_pct),
+            mean(&w.disk_busy_pct),
+            mean(&w.pcie_pct),
+            if w.psi {
+                format!(
+                    " Stall time (PSI): CPU {:.1}%, memory {:.1}%, I/O {:.1}%.",
+                    mean(&w.psi_cpu),
+                    mean(&w.psi_memory),
+                    mean(&w.psi_io)
+                )
+            } else {
+                String::new()
+            }
+        ),
+        State::Unknown => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{DiskDeviceMetrics, GpuMetrics, Pressure, PressureLine, Psi};
+    use crate::sources::{Collector, GpuSource, MockGpuBackend, SourceHealth, SourceStatus};
+    use std::time::SystemTime;
+
+    // Replays a GPU script through the same path as the sampler thread:
+    // Collector -> HardwareMetrics -> History -> analyze.
+    #[test]
+    fn scripted_pcie_saturation_is_pcie_bound() {
+        // Host-to-device copies at 13-15 GB/s, against the default 15.75 GB/s
+        // ceiling since the cards don't report their link.
+        let frame = |pcie_rx: u64, util_pct: u32| {
+            vec![GpuMetrics {
+                name: "A100".to_string(),
+                vram_total_mb: 40960,
+                pcie_rx,
+                util_pct: Some(util_pct),
+                ..Default::default()
+            }]
+        };
+        let script = vec![frame(13_000_000_000, 40), frame(15_000_000_000, 35), frame(14_000_000_000, 45)];
+        let mut collector = Collector::new();
+        collector.register(Box::new(GpuSource::new(Box::new(MockGpuBackend::scripted(script)), Vec::new())));
+        collector.init();
+
+        let mut history = History::new(ANALYSIS_WINDOW);
+        let mut current = HardwareMetrics::default();
+        for second in 0..10 {
+            current.timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + second);
+            collector.sample(&mut current);
+            current.sources = collector.statuses();
+            history.push(&current);
+            if second == 2 {
+                assert_eq!(analyze(&history, &current, &Thresholds::default()).state, State::Unknown);
+            }
+        }
+
+        // Three frames, looping: the tenth sample is the first frame again.
+        assert_eq!(current.gpus[0].pcie_rx, 13_000_000_000);
+        let verdict = analyze(&history, &current, &Thresholds::default());
+        assert_eq!(verdict.state, State::PcieBound);
+        assert_eq!(verdict.confidence, 1.0);
+        assert!(verdict.explanation.contains("the 15.8 GB/s link for 100% of the last 9 s"), "{}", verdict.explanation);
+
+        // The same traffic is fine on a ceiling twice as high.
+        let wide = Thresholds { pcie_ceiling_bps: 31.5e9, ..Default::default() };
+        assert_eq!(analyze(&history, &current, &wide).state, State::Balanced);
+    }
+
+    // Ten seconds of a saturated disk with tasks stalled on I/O a quarter of
+    // the time, with or without the psi source running.
+    fn disk_bound(psi_source: bool) -> Verdict {
+        let mut history = History::new(ANALYSIS_WINDOW);
+        let mut m = HardwareMetrics::default();
+        for second in 0..10 {
+            m = HardwareMetrics {
+                timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + second),
+                cpu_usage: 30.0,
+                ram_total_gb: 64.0,
+                ram_used_gb: 8.0,
+                disks: vec![DiskDeviceMetrics { name: "nvme0n1".to_string(), busy_pct: 97.0, ..Default::default() }],
+                psi: Psi {
+                    io: Some(Pressure { some: PressureLine { avg10: 25.0, ..Default::default() }, full: None }),
+                    ..Default::default()
+                },
+                sources: psi_source
+                    .then(|| SourceStatus { id: "psi".to_string(), description: String::new(), enabled: true, health: SourceHealth::Ok })
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            };
+            history.push(&m);
+        }
+        analyze(&history, &m, &Thresholds::default())
+    }
+
+    #[test]
+    fn stall_sentence_only_with_psi() {
+        let with = disk_bound(true);
+        assert_eq!(with.state, State::DiskBound);
+        assert!(with.explanation.ends_with(" Tasks were stalled on I/O 25.0% of the time."), "{}", with.explanation);
+
+        let without = disk_bound(false);
+        assert_eq!(without.state, State::DiskBound);
+        assert!(!without.explanation.contains("stalled"), "{}", without.explanation);
+        assert!(without.explanation.ends_with("keep the GPU fed."));
+    }
+}
@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+use crate::config::{self, Config};
+use crate::metrics::Metric;
+
+pub const USAGE: &str = "\
+Usage: hardware_monitor [OPTIONS]
+
+Options:
+  --config <file>        Config file [default: <config dir>/hw-mon/config.toml]
+  --profile <name>       Apply a named profile from the config file
+  --disable <id>         Switch off a source (cpu, gpu, disk, net, procs,
+                         cgroup, psi, sensors, power, nvme). Repeatable.
+  --interval <ms>        Sample interval in milliseconds [default: 500]; the GUI
+                         repaints at the same rate unless repaint_ms is set
+  --history <seconds>    How far back the GUI plots go [default: 600]
+  --listen <addr>        Serve OpenMetrics at http://<addr>/metrics (e.g. 127.0.0.1:9184)
+  --record <file>        Write every sample to a recording file
+  --replay <file>        Play a recording back in the GUI instead of polling
+  --headless             Print samples to stdout instead of opening a window
+  --format <table|json>  Headless output format [default: table]
+  --count <n>            Headless: stop after n samples
+  --metrics <list>       Headless: comma-separated metrics to print
+                         (cpu, ram, vram, vram_pct, gpu_util, pcie_tx, pcie_rx, pcie_link,
+                         disk_read, disk_write, disk_busy, net_rx, net_tx,
+                         psi_cpu, psi_memory, psi_io, temp_max, power_package,
+                         power_core, power_uncore, power_dram, gb_per_joule)
+  -h, --help             Print this help
+
+Command-line flags override the config file.
+Units: cpu, vram_pct, gpu_util, pcie_link, disk_busy and psi_* in %, ram in GB, vram in MB, bandwidths in MB/s,
+temp_max in °C, power_* in W, gb_per_joule in GB/J.";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    JsonLines,
+}
+
+pub struct Options {
+    // Loaded from the config file, with the overriding flags already applied.
+    pub config: Config,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    pub format: OutputFormat,
+    pub count: Option<u64>,
+    pub metrics: Vec<Metric>,
+    pub help: bool,
+}
+
+// Parses the arguments after the program name. The config file (and profile)
+// are loaded first so the remaining flags can override what it says.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let args: Vec<String> = args.into_iter().collect();
+    let flag_value = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1));
+    let config_path = flag_value("--config").map(PathBuf::from);
+    let profile = flag_value("--profile").cloned();
+    let config = config::load(config_path.as_deref(), profile.as_deref())?;
+
+    let mut options = Options {
+        config,
+        record: None,
+        replay: None,
+        headless: false,
+        format: OutputFormat::Table,
+        count: None,
+        metrics: Metric::ALL.to_vec(),
+        help: false,
+    };
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            // Already handled above.
+            "--config" | "--profile" => {
+                value(&arg)?;
+            }
+            "--disable" => options.config.sources.disabled.push(value("--disable")?),
+            "--interval" => options.config.intervals.sample_ms = parse_positive(&value("--interval")?, "--interval")?,
+            "--history" => options.config.intervals.history_secs = parse_positive(&value("--history")?, "--history")?,
+            "--listen" => options.config.exporter.listen = Some(value("--listen")?),
+            "--record" => options.record = Some(PathBuf::from(value("--record")?)),
+            "--replay" => options.replay = Some(PathBuf::from(value("--replay")?)),
+            "--headless" => options.headless = true,
+            "--format" => {
+                options.format = match value("--format")?.as_str() {
+                    "table" => OutputFormat::Table,
+                    "json" => OutputFormat::JsonLines,
+                    other => return Err(format!("unknown format '{}' (expected table or json)", other)),
+                }
+            }
+            "--count" => options.count = Some(parse_positive(&value("--count")?, "--count")?),
+            "--metrics" => {
+                options.metrics = value("--metrics")?
+                    .split(',')
+                    .map(|key| Metric::from_key(key.trim()).ok_or_else(|| format!("unknown metric '{}'", key)))
+                    .collect::<Result<_, _>>()?;
+            }
+            "-h" | "--help" => options.help = true,
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    if options.headless && options.replay.is_some() {
+        return Err("--replay needs the GUI and cannot be combined with --headless".to_string());
+    }
+    if options.record.is_some() && options.replay.is_some() {
+        return Err("--record and --replay cannot be used together".to_string());
+    }
+    Ok(options)
+}
+
+fn parse_positive(value: &str, name: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("{} needs a positive integer, got '{}'", name, value)),
+    }
+}
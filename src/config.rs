@@ -0,0 +1,308 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::alerts::AlertsConfig;
+use crate::analysis::Thresholds;
+
+// Settings that can come from the TOML config file. Every section and field
+// is optional; anything left out keeps the default below. Command-line flags
+// are applied on top of the loaded config.
+//
+// A file may also define named profiles that overlay the top-level settings:
+//
+//   profile = "training"            # used when --profile is not given
+//
+//   [intervals]
+//   sample_ms = 500
+//
+//   [profiles.training.intervals]
+//   sample_ms = 250
+//
+//   [profiles.storage-bench.sources]
+//   disabled = ["gpu"]
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub sources: SourcesConfig,
+    pub intervals: IntervalsConfig,
+    pub units: UnitsConfig,
+    pub thresholds: Thresholds,
+    pub exporter: ExporterConfig,
+    pub layout: LayoutConfig,
+    pub alerts: AlertsConfig,
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourcesConfig {
+    // Source ids to switch off, e.g. ["gpu"].
+    pub disabled: Vec<String>,
+    // NVML device indices of the GPUs to monitor; empty means all of them.
+    pub gpus: Vec<u32>,
+    // Replace the GPU backend with this many fake GPUs, for trying out the
+    // GPU panels on machines without one. 0 = off. Only in builds with the
+    // mock-gpu feature; elsewhere the key is rejected like any unknown one.
+    #[cfg(feature = "mock-gpu")]
+    pub mock_gpus: u32,
+    // Like mock_gpus, but replaying readings from a JSON-lines script, one
+    // frame per sample. See mock_gpu::parse_script for the format.
+    #[cfg(feature = "mock-gpu")]
+    pub mock_gpu_script: Option<PathBuf>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntervalsConfig {
+    pub sample_ms: u64,
+    // How often the GUI redraws; follows sample_ms when left out.
+    pub repaint_ms: Option<u64>,
+    pub history_secs: u64,
+}
+
+impl Default for IntervalsConfig {
+    fn default() -> Self {
+        Self { sample_ms: 500, repaint_ms: None, history_secs: 600 }
+    }
+}
+
+impl IntervalsConfig {
+    pub fn sample(&self) -> Duration {
+        Duration::from_millis(self.sample_ms)
+    }
+
+    pub fn repaint(&self) -> Duration {
+        Duration::from_millis(self.repaint_ms.unwrap_or(self.sample_ms))
+    }
+
+    pub fn history(&self) -> Duration {
+        Duration::from_secs(self.history_secs)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Default, PartialEq)]
+pub enum BandwidthUnit {
+    #[default]
+    #[serde(rename = "MiB/s")]
+    MiB,
+    #[serde(rename = "MB/s")]
+    MB,
+    #[serde(rename = "GiB/s")]
+    GiB,
+    #[serde(rename = "GB/s")]
+    GB,
+}
+
+impl BandwidthUnit {
+    fn divisor(self) -> f64 {
+        match self {
+            BandwidthUnit::MiB => 1024.0 * 1024.0,
+            BandwidthUnit::MB => 1e6,
+            BandwidthUnit::GiB => 1024.0 * 1024.0 * 1024.0,
+            BandwidthUnit::GB => 1e9,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BandwidthUnit::MiB => "MiB/s",
+            BandwidthUnit::MB => "MB/s",
+            BandwidthUnit::GiB => "GiB/s",
+            BandwidthUnit::GB => "GB/s",
+        }
+    }
+
+    pub fn convert(self, bytes_per_sec: f64) -> f64 {
+        bytes_per_sec / self.divisor()
+    }
+
+    // e.g. "12.34 MiB/s"
+    pub fn format(self, bytes_per_sec: u64) -> String {
+        format!("{:.2} {}", self.convert(bytes_per_sec as f64), self.label())
+    }
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct UnitsConfig {
+    // How bandwidths are shown in the GUI. Headless and exporter output keep
+    // their documented fixed units.
+    pub bandwidth: BandwidthUnit,
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExporterConfig {
+    // Address for the OpenMetrics endpoint, e.g. "127.0.0.1:9184".
+    pub listen: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    pub icon: PathBuf,
+    pub show_history: bool,
+    pub show_cores: bool,
+    pub show_disk_table: bool,
+    pub show_network_table: bool,
+    pub show_nvme_health: bool,
+    pub show_processes: bool,
+    pub show_cgroups: bool,
+    pub show_sensors: bool,
+    pub show_analysis: bool,
+    pub show_topology: bool,
+    pub window_size: Option<[f32; 2]>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            icon: PathBuf::from("assets/favicon.ico"),
+            show_history: true,
+            show_cores: true,
+            show_disk_table: true,
+            show_network_table: true,
+            show_nvme_health: true,
+            show_processes: true,
+            show_cgroups: true,
+            show_sensors: true,
+            show_analysis: true,
+            show_topology: true,
+            window_size: None,
+        }
+    }
+}
+
+// $XDG_CONFIG_HOME/hw-mon/config.toml on Linux, %APPDATA%\hw-mon\config.toml on Windows.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("hw-mon").join("config.toml"))
+}
+
+// Loads the config from `path`, or from the default location if that file
+// exists, or falls back to the built-in defaults. `profile` overrides the
+// file's own `profile` key.
+pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Config, String> {
+    let text = match path {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => match default_path().filter(|p| p.is_file()) {
+            Some(path) => std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?,
+            None if profile.is_some() => return Err("--profile needs a config file".to_string()),
+            None => return Ok(Config::default()),
+        },
+    };
+    parse(&text, profile)
+}
+
+pub fn parse(text: &str, profile: Option<&str>) -> Result<Config, String> {
+    let mut table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+
+    let profiles = match table.remove("profiles") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => return Err("`profiles` must be a table".to_string()),
+        None => toml::Table::new(),
+    };
+    let default_profile = match table.remove("profile") {
+        Some(toml::Value::String(name)) => Some(name),
+        Some(_) => return Err("`profile` must be a string".to_string()),
+        None => None,
+    };
+
+    if let Some(name) = profile.map(str::to_string).or(default_profile) {
+        match profiles.get(&name) {
+            Some(toml::Value::Table(overlay)) => merge(&mut table, overlay),
+            _ => {
+                let known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                return Err(format!("unknown profile '{}' (available: {})", name, known.join(", ")));
+            }
+        }
+    }
+
+    let config: Config = toml::Value::Table(table).try_into().map_err(|e: toml::de::Error| e.to_string())?;
+    let intervals = &config.intervals;
+    if intervals.sample_ms == 0 || intervals.repaint_ms == Some(0) || intervals.history_secs == 0 {
+        return Err("intervals must be greater than zero".to_string());
+    }
+    config.alerts.validate()?;
+    Ok(config)
+}
+
+// Recursively overlays `overlay` onto `base`: tables merge, anything else replaces.
+fn merge(base: &mut toml::Table, overlay: &toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repaint_follows_the_sample_interval_unless_set() {
+        let config = parse("[intervals]\nsample_ms = 100\n", None).unwrap();
+        assert_eq!(config.intervals.repaint(), Duration::from_millis(100));
+        let config = parse("[intervals]\nsample_ms = 100\nrepaint_ms = 250\n", None).unwrap();
+        assert_eq!(config.intervals.repaint(), Duration::from_millis(250));
+        assert!(parse("[intervals]\nrepaint_ms = 0\n", None).is_err());
+    }
+
+    const PROFILES: &str = r#"
+profile = "training"
+
+[sources]
+gpus = [0, 1]
+
+[intervals]
+sample_ms = 500
+history_secs = 300
+
+[layout]
+show_history = false
+
+[profiles.training.intervals]
+sample_ms = 250
+
+[profiles.storage-bench.sources]
+disabled = ["gpu"]
+"#;
+
+    #[test]
+    fn profile_overlays_the_base_settings() {
+        // The file's own default profile: only sample_ms changes.
+        let training = parse(PROFILES, None).unwrap();
+        assert_eq!((training.intervals.sample_ms, training.intervals.history_secs), (250, 300));
+        assert_eq!(training.sources.gpus, [0, 1]);
+        assert!(!training.layout.show_history);
+
+        // --profile wins over the file's; tables merge key by key.
+        let bench = parse(PROFILES, Some("storage-bench")).unwrap();
+        assert_eq!(bench.intervals.sample_ms, 500);
+        assert_eq!(bench.sources.disabled, ["gpu"]);
+        assert_eq!(bench.sources.gpus, [0, 1]);
+
+        let base = parse(&PROFILES.replace("profile = \"training\"", ""), None).unwrap();
+        assert_eq!(base.intervals.sample_ms, 500);
+    }
+
+    #[test]
+    fn unknown_profile_is_an_error() {
+        let Err(e) = parse(PROFILES, Some("inference")) else { panic!("profile accepted") };
+        assert_eq!(e, "unknown profile 'inference' (available: storage-bench, training)");
+        assert!(parse("profile = \"training\"\n", None).is_err());
+        assert!(parse("profile = 3\n", None).is_err());
+    }
+
+    #[test]
+    fn rejects_misspelled_keys() {
+        let Err(e) = parse("[intervals]\nsample_msec = 100\n", None) else { panic!("key accepted") };
+        assert!(e.contains("sample_msec"), "{}", e);
+        assert!(parse("[layuot]\nshow_history = false\n", None).is_err());
+        // Inside a profile too, once it is applied.
+        assert!(parse("[profiles.fast.intervals]\nsampel_ms = 100\n", Some("fast")).is_err());
+    }
+}
@@ -0,0 +1,586 @@
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::metrics::HardwareMetrics;
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+// Serves the latest sample at GET /metrics in OpenMetrics text format.
+// Runs on its own thread and only ever takes the metrics lock long enough to
+// render one response.
+pub fn spawn(addr: &str, metrics: Arc<Mutex<HardwareMetrics>>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // Scrapes are tiny and infrequent, so one connection at a time is fine.
+            let _ = handle(stream, &metrics);
+        }
+    });
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, metrics: &Mutex<HardwareMetrics>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers so the client isn't reset while still sending them.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    // Ignore the query string, Prometheus may append one.
+    let path = path.split('?').next().unwrap_or("");
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") => {
+            let body = render(&metrics.lock().unwrap());
+            ("200 OK", CONTENT_TYPE, body)
+        }
+        ("GET", _) => ("404 Not Found", "text/plain", "Not found. Metrics are at /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Gauge,
+    Counter,
+    // Identity labels on a sample that is always 1.
+    Info,
+}
+
+// Accumulates metric families. Every family gets its TYPE/UNIT/HELP header
+// once, followed by its samples; counter samples get the `_total` suffix and
+// info samples the `_info` suffix.
+struct Writer {
+    out: String,
+}
+
+impl Writer {
+    fn family(&mut self, name: &str, kind: Kind, unit: &str, help: &str) {
+        let kind_name = match kind {
+            Kind::Gauge => "gauge",
+            Kind::Counter => "counter",
+            Kind::Info => "info",
+        };
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind_name);
+        if !unit.is_empty() {
+            let _ = writeln!(self.out, "# UNIT {} {}", name, unit);
+        }
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+    }
+
+    fn sample(&mut self, name: &str, kind: Kind, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        match kind {
+            Kind::Gauge => {}
+            Kind::Counter => self.out.push_str("_total"),
+            Kind::Info => self.out.push_str("_info"),
+        }
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (key, val)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{}=\"{}\"", key, escape_label(val));
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {}", format_value(value));
+    }
+
+    // Shorthand for a family with a single unlabeled sample.
+    fn single(&mut self, name: &str, kind: Kind, unit: &str, help: &str, value: f64) {
+        self.family(name, kind, unit, help);
+        self.sample(name, kind, &[], value);
+    }
+}
+
+// OpenMetrics spells the non-finite values +Inf, -Inf and NaN; Rust's
+// Display would write inf and NaN.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"").replace('\n', r"\n")
+}
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+const MIB: f64 = 1024.0 * 1024.0;
+
+// Pressure stall information: the averages as ratios of wall time, and the
+// total stall time as a counter, labelled by resource and some/full.
+fn emit_pressure(w: &mut Writer, psi: &crate::metrics::Psi) {
+    let mut lines = Vec::new();
+    for (resource, pressure) in [("cpu", &psi.cpu), ("memory", &psi.memory), ("io", &psi.io)] {
+        let Some(pressure) = pressure else { continue };
+        lines.push((resource, "some", pressure.some));
+        if let Some(full) = pressure.full {
+            lines.push((resource, "full", full));
+        }
+    }
+
+    let name = "hwmon_pressure_stalled_ratio";
+    w.family(name, Kind::Gauge, "ratio", "Share of wall time tasks were stalled on a resource, averaged over a window.");
+    for &(resource, kind, line) in &lines {
+        for (window, avg) in [("10s", line.avg10), ("60s", line.avg60), ("300s", line.avg300)] {
+            w.sample(name, Kind::Gauge, &[("resource", resource), ("kind", kind), ("window", window)], avg as f64 / 100.0);
+        }
+    }
+    let name = "hwmon_pressure_stalled_seconds";
+    w.family(name, Kind::Counter, "seconds", "Time tasks were stalled on a resource.");
+    for &(resource, kind, line) in &lines {
+        w.sample(name, Kind::Counter, &[("resource", resource), ("kind", kind)], line.total_usec as f64 / 1e6);
+    }
+}
+
+// hwmon readings and their thresholds, one family per kind, labelled by chip
+// driver, device and sensor label.
+fn emit_sensors(w: &mut Writer, chips: &[crate::metrics::SensorChip]) {
+    use crate::metrics::{SensorKind, SensorReading};
+
+    type Threshold = fn(&SensorReading) -> Option<f64>;
+    let kinds = [
+        (SensorKind::Temperature, "temperature", "celsius", "Temperature"),
+        (SensorKind::Fan, "fan_speed", "rpm", "Fan speed"),
+        (SensorKind::Voltage, "voltage", "volts", "Voltage"),
+        (SensorKind::Power, "power", "watts", "Power draw"),
+    ];
+    let variants: [(&str, &str, Threshold); 3] = [
+        ("", " reported by a hwmon sensor.", |s| Some(s.value)),
+        ("_max", " maximum threshold of a hwmon sensor.", |s| s.max),
+        ("_crit", " critical threshold of a hwmon sensor.", |s| s.crit),
+    ];
+    for (kind, base, unit, help) in kinds {
+        for (suffix, help_suffix, field) in variants {
+            let name = format!("hwmon_sensor_{}{}_{}", base, suffix, unit);
+            let samples: Vec<(&crate::metrics::SensorChip, &SensorReading, f64)> = chips
+                .iter()
+                .flat_map(|chip| chip.sensors.iter().map(move |s| (chip, s)))
+                .filter(|(_, s)| s.kind == kind)
+                .filter_map(|(chip, s)| Some((chip, s, field(s)?)))
+                .collect();
+            if samples.is_empty() {
+                continue;
+            }
+            w.family(&name, Kind::Gauge, unit, &format!("{}{}", help, help_suffix));
+            for (chip, sensor, value) in samples {
+                let labels = [("chip", chip.name.as_str()), ("device", chip.device.as_str()), ("sensor", sensor.label.as_str())];
+                w.sample(&name, Kind::Gauge, &labels, value);
+            }
+        }
+    }
+}
+
+// NVMe identity as an info metric, temperature, and the SMART log fields
+// for controllers whose log could be read.
+fn emit_nvme(w: &mut Writer, controllers: &[crate::metrics::NvmeController]) {
+    let name = "hwmon_nvme";
+    w.family(name, Kind::Info, "", "NVMe controller identity.");
+    for c in controllers {
+        let labels = [
+            ("controller", c.name.as_str()),
+            ("model", c.model.as_str()),
+            ("serial", c.serial.as_str()),
+            ("firmware", c.firmware.as_str()),
+            ("transport", c.transport.as_str()),
+            ("state", c.state.as_str()),
+        ];
+        w.sample(name, Kind::Info, &labels, 1.0);
+    }
+    let name = "hwmon_nvme_temperature_celsius";
+    w.family(name, Kind::Gauge, "celsius", "Composite temperature from the controller's hwmon chip.");
+    for c in controllers {
+        if let Some(celsius) = c.temperature_c {
+            w.sample(name, Kind::Gauge, &[("controller", c.name.as_str())], celsius);
+        }
+    }
+    let name = "hwmon_nvme_smart_readable";
+    w.family(name, Kind::Gauge, "", "Whether the SMART log could be read (usually needs root).");
+    for c in controllers {
+        w.sample(name, Kind::Gauge, &[("controller", c.name.as_str())], if c.smart.is_ok() { 1.0 } else { 0.0 });
+    }
+
+    type SmartField = fn(&crate::metrics::SmartLog) -> f64;
+    let smart: [(&str, Kind, &str, &str, SmartField); 8] = [
+        ("hwmon_nvme_critical_warning", Kind::Gauge, "", "SMART critical warning bit field; 0 when healthy.", |s| s.critical_warning as f64),
+        ("hwmon_nvme_percentage_used_ratio", Kind::Gauge, "ratio", "Share of rated endurance used; may exceed 1.", |s| s.percentage_used as f64 / 100.0),
+        ("hwmon_nvme_available_spare_ratio", Kind::Gauge, "ratio", "Remaining spare capacity.", |s| s.available_spare_pct as f64 / 100.0),
+        ("hwmon_nvme_available_spare_threshold_ratio", Kind::Gauge, "ratio", "Spare capacity below which the drive warns.", |s| s.available_spare_threshold_pct as f64 / 100.0),
+        ("hwmon_nvme_media_errors", Kind::Counter, "", "Unrecovered data integrity errors.", |s| s.media_errors as f64),
+        ("hwmon_nvme_unsafe_shutdowns", Kind::Counter, "", "Power losses without a shutdown notification.", |s| s.unsafe_shutdowns as f64),
+        ("hwmon_nvme_data_read_bytes", Kind::Counter, "bytes", "Data read by the host over the drive's life.", |s| s.data_read_bytes as f64),
+        ("hwmon_nvme_data_written_bytes", Kind::Counter, "bytes", "Data written by the host over the drive's life.", |s| s.data_written_bytes as f64),
+    ];
+    for (name, kind, unit, help, field) in smart {
+        w.family(name, kind, unit, help);
+        for c in controllers {
+            if let Ok(log) = &c.smart {
+                w.sample(name, kind, &[("controller", c.name.as_str())], field(log));
+            }
+        }
+    }
+}
+
+// Renders a full exposition, metric values in base units (bytes, seconds,
+// ratios) as the OpenMetrics conventions ask.
+pub fn render(m: &HardwareMetrics) -> String {
+    let mut w = Writer { out: String::new() };
+
+    w.family("hwmon_source_up", Kind::Gauge, "", "Whether a collection source is enabled and working.");
+    for status in &m.sources {
+        let up = if m.has_source(&status.id) { 1.0 } else { 0.0 };
+        w.sample("hwmon_source_up", Kind::Gauge, &[("source", status.id.as_str())], up);
+    }
+
+    if m.has_source("cpu") {
+        w.single("hwmon_cpu_utilization_ratio", Kind::Gauge, "ratio", "Global CPU utilization.", m.cpu_usage as f64 / 100.0);
+        w.single("hwmon_memory_used_bytes", Kind::Gauge, "bytes", "Used system memory.", m.ram_used_gb as f64 * GIB);
+        w.single("hwmon_memory_total_bytes", Kind::Gauge, "bytes", "Total system memory.", m.ram_total_gb as f64 * GIB);
+
+        if let Some(t) = &m.cpu_times {
+            let name = "hwmon_cpu_mode_ratio";
+            w.family(name, Kind::Gauge, "ratio", "Share of CPU time spent in each mode.");
+            let modes = [
+                ("user", t.user),
+                ("nice", t.nice),
+                ("system", t.system),
+                ("iowait", t.iowait),
+                ("irq", t.irq),
+                ("softirq", t.softirq),
+                ("steal", t.steal),
+            ];
+            for (mode, pct) in modes {
+                w.sample(name, Kind::Gauge, &[("mode", mode)], pct as f64 / 100.0);
+            }
+        }
+        if !m.cores.is_empty() {
+            type CoreField = fn(&crate::metrics::CoreMetrics) -> f64;
+            let per_core: [(&str, &str, &str, CoreField); 2] = [
+                ("hwmon_cpu_core_utilization_ratio", "ratio", "Per logical CPU utilization.", |c| c.usage as f64 / 100.0),
+                ("hwmon_cpu_core_frequency_hertz", "hertz", "Per logical CPU current clock.", |c| c.freq_mhz as f64 * 1e6),
+            ];
+            for (name, unit, help, field) in per_core {
+                w.family(name, Kind::Gauge, unit, help);
+                for core in &m.cores {
+                    let (cpu, core_id, package) = (core.cpu.to_string(), core.core_id.to_string(), core.package_id.to_string());
+                    let labels = [("cpu", cpu.as_str()), ("core", core_id.as_str()), ("package", package.as_str())];
+                    w.sample(name, Kind::Gauge, &labels, field(core));
+                }
+            }
+        }
+        if let Some(mem) = &m.memory {
+            let gauges = [
+                ("hwmon_memory_available_bytes", "Memory available without swapping.", mem.available_bytes),
+                ("hwmon_memory_cached_bytes", "Page cache.", mem.cached_bytes),
+                ("hwmon_memory_buffers_bytes", "Block device buffers.", mem.buffers_bytes),
+                ("hwmon_memory_dirty_bytes", "Memory waiting to be written back to disk.", mem.dirty_bytes),
+                ("hwmon_swap_used_bytes", "Used swap space.", mem.swap_used_bytes),
+                ("hwmon_swap_total_bytes", "Total swap space.", mem.swap_total_bytes),
+            ];
+            for (name, help, value) in gauges {
+                w.single(name, Kind::Gauge, "bytes", help, value as f64);
+            }
+        }
+    }
+
+    if m.has_source("psi") {
+        emit_pressure(&mut w, &m.psi);
+    }
+
+    if m.has_source("sensors") {
+        emit_sensors(&mut w, &m.sensors);
+    }
+
+    if m.has_source("power") {
+        type PowerField = fn(&crate::metrics::PowerDomain) -> f64;
+        let per_domain: [(&str, Kind, &str, &str, PowerField); 2] = [
+            ("hwmon_rapl_power_watts", Kind::Gauge, "watts", "Mean power of a RAPL domain over the last sample interval.", |d| d.watts as f64),
+            ("hwmon_rapl_energy_joules", Kind::Counter, "joules", "Energy used by a RAPL domain since the monitor started.", |d| d.energy_joules),
+        ];
+        for (name, kind, unit, help, field) in per_domain {
+            w.family(name, kind, unit, help);
+            for domain in &m.power {
+                let labels = [("zone", domain.zone.as_str()), ("package", domain.package.as_str()), ("domain", domain.kind())];
+                w.sample(name, kind, &labels, field(domain));
+            }
+        }
+        if let Some(bytes) = m.bytes_per_joule() {
+            w.single(
+                "hwmon_data_moved_bytes_per_joule",
+                Kind::Gauge,
+                "",
+                "Disk, PCIe and network traffic per joule of CPU package and DRAM energy.",
+                bytes,
+            );
+        }
+    }
+
+    if m.has_source("gpu") {
+        // Optional readings are left out for cards that don't support them.
+        type GpuField = fn(&crate::metrics::GpuMetrics) -> Option<f64>;
+        let per_gpu: [(&str, &str, &str, GpuField); 19] = [
+            ("hwmon_gpu_memory_used_bytes", "bytes", "Used GPU memory.", |g| Some(g.vram_used_mb as f64 * MIB)),
+            ("hwmon_gpu_memory_total_bytes", "bytes", "Total GPU memory.", |g| Some(g.vram_total_mb as f64 * MIB)),
+            ("hwmon_gpu_pcie_tx_bytes_per_second", "bytes_per_second", "PCIe throughput from the GPU.", |g| Some(g.pcie_tx as f64)),
+            ("hwmon_gpu_pcie_rx_bytes_per_second", "bytes_per_second", "PCIe throughput to the GPU.", |g| Some(g.pcie_rx as f64)),
+            ("hwmon_gpu_utilization_ratio", "ratio", "Share of time a kernel was running.", |g| g.util_pct.map(|v| v as f64 / 100.0)),
+            ("hwmon_gpu_memory_utilization_ratio", "ratio", "Share of time device memory was being accessed.", |g| g.mem_util_pct.map(|v| v as f64 / 100.0)),
+            ("hwmon_gpu_graphics_clock_hertz", "hertz", "Current graphics clock.", |g| g.graphics_clock_mhz.map(|v| v as f64 * 1e6)),
+            ("hwmon_gpu_memory_clock_hertz", "hertz", "Current memory clock.", |g| g.memory_clock_mhz.map(|v| v as f64 * 1e6)),
+            ("hwmon_gpu_graphics_clock_max_hertz", "hertz", "Highest supported graphics clock.", |g| g.max_graphics_clock_mhz.map(|v| v as f64 * 1e6)),
+            ("hwmon_gpu_memory_clock_max_hertz", "hertz", "Highest supported memory clock.", |g| g.max_memory_clock_mhz.map(|v| v as f64 * 1e6)),
+            ("hwmon_gpu_temperature_celsius", "celsius", "GPU core temperature.", |g| g.temperature_c.map(|v| v as f64)),
+            ("hwmon_gpu_power_watts", "watts", "Current power draw.", |g| g.power_w.map(|v| v as f64)),
+            ("hwmon_gpu_power_limit_watts", "watts", "Enforced power limit.", |g| g.power_limit_w.map(|v| v as f64)),
+            ("hwmon_gpu_pcie_link_generation", "", "Negotiated PCIe generation.", |g| g.pcie_link.map(|l| l.gen as f64)),
+            ("hwmon_gpu_pcie_link_width", "", "Negotiated PCIe lane count.", |g| g.pcie_link.map(|l| l.width as f64)),
+            ("hwmon_gpu_pcie_link_max_generation", "", "Highest supported PCIe generation.", |g| g.pcie_link.map(|l| l.max_gen as f64)),
+            ("hwmon_gpu_pcie_link_max_width", "", "Highest supported PCIe lane count.", |g| g.pcie_link.map(|l| l.max_width as f64)),
+            ("hwmon_gpu_pcie_link_bandwidth_bytes_per_second", "bytes_per_second", "Theoretical per-direction bandwidth of the negotiated link.", |g| g.pcie_link.and_then(|l| l.bandwidth_bps())),
+            ("hwmon_gpu_pcie_link_utilization_ratio", "ratio", "Busier PCIe direction as a share of the negotiated link.", |g| g.pcie_util_pct().map(|v| v as f64 / 100.0)),
+        ];
+        for (name, unit, help, field) in per_gpu {
+            w.family(name, Kind::Gauge, unit, help);
+            for gpu in &m.gpus {
+                let Some(value) = field(gpu) else { continue };
+                let index = gpu.index.to_string();
+                let labels = [
+                    ("gpu", index.as_str()),
+                    ("name", gpu.name.as_str()),
+                    ("uuid", gpu.uuid.as_str()),
+                    ("pci_bus_id", gpu.pci_bus_id.as_str()),
+                ];
+                w.sample(name, Kind::Gauge, &labels, value);
+            }
+        }
+
+        // One series per active reason, so alerts can match on the label.
+        let name = "hwmon_gpu_throttle_active";
+        w.family(name, Kind::Gauge, "", "Clock throttle reasons currently in effect.");
+        for gpu in &m.gpus {
+            let index = gpu.index.to_string();
+            for reason in &gpu.throttle_reasons {
+                w.sample(name, Kind::Gauge, &[("gpu", index.as_str()), ("reason", reason.label())], 1.0);
+            }
+        }
+    }
+
+    if m.has_source("disk") {
+        w.single(
+            "hwmon_disk_read_bytes_per_second",
+            Kind::Gauge,
+            "bytes_per_second",
+            "Read bandwidth summed over physical disks.",
+            m.disk_read_bps as f64,
+        );
+        w.single(
+            "hwmon_disk_write_bytes_per_second",
+            Kind::Gauge,
+            "bytes_per_second",
+            "Write bandwidth summed over physical disks.",
+            m.disk_write_bps as f64,
+        );
+
+        type DiskField = fn(&crate::metrics::DiskDeviceMetrics) -> f64;
+        let per_device: [(&str, Kind, &str, &str, DiskField); 11] = [
+            ("hwmon_disk_device_read_bytes_per_second", Kind::Gauge, "bytes_per_second", "Per-device read bandwidth.", |d| d.read_bps as f64),
+            ("hwmon_disk_device_write_bytes_per_second", Kind::Gauge, "bytes_per_second", "Per-device write bandwidth.", |d| d.write_bps as f64),
+            ("hwmon_disk_device_reads_per_second", Kind::Gauge, "", "Per-device completed reads per second.", |d| d.read_iops as f64),
+            ("hwmon_disk_device_writes_per_second", Kind::Gauge, "", "Per-device completed writes per second.", |d| d.write_iops as f64),
+            ("hwmon_disk_device_io_latency_seconds", Kind::Gauge, "seconds", "Per-device mean time per completed I/O.", |d| d.avg_latency_ms as f64 / 1000.0),
+            ("hwmon_disk_device_queue_depth", Kind::Gauge, "", "Per-device average number of I/Os in flight.", |d| d.queue_depth as f64),
+            ("hwmon_disk_device_busy_ratio", Kind::Gauge, "ratio", "Per-device share of time with I/O in flight.", |d| d.busy_pct as f64 / 100.0),
+            ("hwmon_disk_device_read_bytes", Kind::Counter, "bytes", "Per-device bytes read.", |d| d.read_bytes_total as f64),
+            ("hwmon_disk_device_written_bytes", Kind::Counter, "bytes", "Per-device bytes written.", |d| d.write_bytes_total as f64),
+            ("hwmon_disk_device_reads", Kind::Counter, "", "Per-device completed reads.", |d| d.reads_total as f64),
+            ("hwmon_disk_device_writes", Kind::Counter, "", "Per-device completed writes.", |d| d.writes_total as f64),
+        ];
+        for (name, kind, unit, help, field) in per_device {
+            w.family(name, kind, unit, help);
+            for disk in &m.disks {
+                w.sample(name, kind, &[("device", disk.name.as_str())], field(disk));
+            }
+        }
+    }
+
+    if m.has_source("nvme") {
+        emit_nvme(&mut w, &m.nvme);
+    }
+
+    if m.has_source("net") {
+        w.single(
+            "hwmon_network_receive_bytes_per_second",
+            Kind::Gauge,
+            "bytes_per_second",
+            "Receive bandwidth summed over interfaces.",
+            m.net_rx_bps as f64,
+        );
+        w.single(
+            "hwmon_network_transmit_bytes_per_second",
+            Kind::Gauge,
+            "bytes_per_second",
+            "Transmit bandwidth summed over interfaces.",
+            m.net_tx_bps as f64,
+        );
+
+        type NetField = fn(&crate::metrics::NetInterfaceMetrics) -> f64;
+        let per_interface: [(&str, Kind, &str, &str, NetField); 13] = [
+            ("hwmon_network_interface_receive_bytes_per_second", Kind::Gauge, "bytes_per_second", "Per-interface receive bandwidth.", |i| i.rx_bps as f64),
+            ("hwmon_network_interface_transmit_bytes_per_second", Kind::Gauge, "bytes_per_second", "Per-interface transmit bandwidth.", |i| i.tx_bps as f64),
+            ("hwmon_network_interface_receive_packets_per_second", Kind::Gauge, "", "Per-interface received packets per second.", |i| i.rx_pps as f64),
+            ("hwmon_network_interface_transmit_packets_per_second", Kind::Gauge, "", "Per-interface transmitted packets per second.", |i| i.tx_pps as f64),
+            ("hwmon_network_interface_speed_bits_per_second", Kind::Gauge, "bits_per_second", "Negotiated link speed, 0 if unknown.", |i| i.link_speed_mbps as f64 * 1e6),
+            ("hwmon_network_interface_received_bytes", Kind::Counter, "bytes", "Per-interface bytes received.", |i| i.rx_bytes_total as f64),
+            ("hwmon_network_interface_transmitted_bytes", Kind::Counter, "bytes", "Per-interface bytes transmitted.", |i| i.tx_bytes_total as f64),
+            ("hwmon_network_interface_received_packets", Kind::Counter, "", "Per-interface packets received.", |i| i.rx_packets_total as f64),
+            ("hwmon_network_interface_transmitted_packets", Kind::Counter, "", "Per-interface packets transmitted.", |i| i.tx_packets_total as f64),
+            ("hwmon_network_interface_receive_errors", Kind::Counter, "", "Per-interface receive errors.", |i| i.rx_errors_total as f64),
+            ("hwmon_network_interface_transmit_errors", Kind::Counter, "", "Per-interface transmit errors.", |i| i.tx_errors_total as f64),
+            ("hwmon_network_interface_receive_drops", Kind::Counter, "", "Per-interface dropped incoming packets.", |i| i.rx_drops_total as f64),
+            ("hwmon_network_interface_transmit_drops", Kind::Counter, "", "Per-interface dropped outgoing packets.", |i| i.tx_drops_total as f64),
+        ];
+        for (name, kind, unit, help, field) in per_interface {
+            w.family(name, kind, unit, help);
+            for iface in &m.interfaces {
+                w.sample(name, kind, &[("interface", iface.name.as_str())], field(iface));
+            }
+        }
+    }
+
+    w.out.push_str("# EOF\n");
+    w.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{NvmeController, PowerDomain, SensorChip, SensorKind, SensorReading, SmartLog};
+    use crate::sources::{SourceHealth, SourceStatus};
+
+    fn status(id: &str) -> SourceStatus {
+        SourceStatus { id: id.to_string(), description: String::new(), enabled: true, health: SourceHealth::Ok }
+    }
+
+    fn sample() -> HardwareMetrics {
+        HardwareMetrics {
+            sources: vec![status("sensors"), status("power"), status("nvme")],
+            sensors: vec![SensorChip {
+                name: "k10temp".to_string(),
+                device: "0000:00:18.3".to_string(),
+                sensors: vec![SensorReading {
+                    kind: SensorKind::Temperature,
+                    label: "C:\\ \"hot\"\nspot".to_string(),
+                    value: 45.5,
+                    max: Some(f64::INFINITY),
+                    crit: Some(f64::NAN),
+                }],
+            }],
+            power: vec![PowerDomain {
+                zone: "intel-rapl:0".to_string(),
+                name: "package-0".to_string(),
+                package: "package-0".to_string(),
+                watts: 12.5,
+                energy_joules: 300.0,
+            }],
+            nvme: vec![NvmeController {
+                name: "nvme0".to_string(),
+                model: "Samsung SSD 980".to_string(),
+                serial: "S123".to_string(),
+                firmware: "1B4QFXO7".to_string(),
+                transport: "pcie".to_string(),
+                address: "0000:01:00.0".to_string(),
+                state: "live".to_string(),
+                temperature_c: Some(f64::NEG_INFINITY),
+                temperature_crit_c: None,
+                smart: Ok(SmartLog::default()),
+            }],
+            ..Default::default()
+        }
+    }
+
+    // The lines of one family, from its TYPE line up to the next family's.
+    fn family<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+        let header = format!("# TYPE {} ", name);
+        let mut lines = text.lines().skip_while(|line| !line.starts_with(&header));
+        let first = lines.next().unwrap_or_else(|| panic!("no family {}", name));
+        std::iter::once(first).chain(lines.take_while(|line| !line.starts_with("# TYPE") && *line != "# EOF")).collect()
+    }
+
+    #[test]
+    fn families_have_headers_and_suffixes() {
+        let text = render(&sample());
+        assert_eq!(
+            family(&text, "hwmon_rapl_energy_joules"),
+            [
+                "# TYPE hwmon_rapl_energy_joules counter",
+                "# UNIT hwmon_rapl_energy_joules joules",
+                "# HELP hwmon_rapl_energy_joules Energy used by a RAPL domain since the monitor started.",
+                "hwmon_rapl_energy_joules_total{zone=\"intel-rapl:0\",package=\"package-0\",domain=\"package\"} 300",
+            ]
+        );
+        assert_eq!(
+            family(&text, "hwmon_rapl_power_watts")[3],
+            "hwmon_rapl_power_watts{zone=\"intel-rapl:0\",package=\"package-0\",domain=\"package\"} 12.5"
+        );
+        // No unit: no UNIT line.
+        let info = family(&text, "hwmon_nvme");
+        assert_eq!(&info[..2], ["# TYPE hwmon_nvme info", "# HELP hwmon_nvme NVMe controller identity."]);
+        assert!(info[2].starts_with("hwmon_nvme_info{controller=\"nvme0\",model=\"Samsung SSD 980\","), "{}", info[2]);
+        assert!(info[2].ends_with("} 1"));
+        assert!(text.ends_with("\n# EOF\n"));
+        assert_eq!(text.matches("# EOF").count(), 1);
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        let text = render(&sample());
+        let line = family(&text, "hwmon_sensor_temperature_celsius")[3];
+        assert_eq!(
+            line,
+            "hwmon_sensor_temperature_celsius{chip=\"k10temp\",device=\"0000:00:18.3\",sensor=\"C:\\\\ \\\"hot\\\"\\nspot\"} 45.5"
+        );
+    }
+
+    #[test]
+    fn writes_non_finite_values() {
+        assert_eq!((format_value(1.0), format_value(0.25), format_value(-3.0)), ("1".into(), "0.25".into(), "-3".into()));
+        let text = render(&sample());
+        assert!(family(&text, "hwmon_sensor_temperature_max_celsius")[3].ends_with("} +Inf"));
+        assert!(family(&text, "hwmon_sensor_temperature_crit_celsius")[3].ends_with("} NaN"));
+        assert!(family(&text, "hwmon_nvme_temperature_celsius")[3].ends_with("} -Inf"));
+    }
+
+    #[test]
+    fn skips_sources_that_are_not_running() {
+        let mut m = sample();
+        m.sources[1].health = SourceHealth::Unavailable("no RAPL zones".to_string());
+        let text = render(&m);
+        assert!(text.contains("hwmon_source_up{source=\"power\"} 0\n"));
+        assert!(!text.contains("hwmon_rapl_"));
+        assert!(text.contains("hwmon_source_up{source=\"sensors\"} 1\n"));
+    }
+}
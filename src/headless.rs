@@ -0,0 +1,117 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+use crate::alerts::Alerts;
+use crate::cli::{Options, OutputFormat};
+use crate::exporter;
+use crate::metrics::{HardwareMetrics, Metric};
+use crate::recording::Recorder;
+use crate::sources::Collector;
+
+// Runs the collector on the current thread and prints one line per sample
+// until `options.count` samples were printed or stdout goes away.
+pub fn run(mut collector: Collector, mut recorder: Option<Recorder>, options: &Options) {
+    collector.init();
+    let mut alerts = Alerts::new(&options.config.alerts, None);
+
+    // Only needed when the exporter runs alongside, to share the latest sample.
+    let shared = options.config.exporter.listen.as_ref().and_then(|addr| {
+        let shared = Arc::new(Mutex::new(HardwareMetrics::default()));
+        match exporter::spawn(addr, shared.clone()) {
+            Ok(()) => Some(shared),
+            Err(e) => {
+                eprintln!("could not listen on {}: {}", addr, e);
+                None
+            }
+        }
+    });
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    if options.format == OutputFormat::Table && writeln!(out, "{}", table_header(&options.metrics)).is_err() {
+        return;
+    }
+
+    let mut current = HardwareMetrics::default();
+    let mut printed = 0;
+    loop {
+        let started = Instant::now();
+        current.timestamp = SystemTime::now();
+        collector.sample(&mut current);
+        current.sources = collector.statuses();
+        alerts.evaluate(&current);
+        if let Some(shared) = &shared {
+            *shared.lock().unwrap() = current.clone();
+        }
+        if let Some(Err(e)) = recorder.as_mut().map(|r| r.record(&current)) {
+            eprintln!("recording stopped: {}", e);
+            recorder = None;
+        }
+
+        let line = match options.format {
+            OutputFormat::Table => table_row(&current, &options.metrics),
+            OutputFormat::JsonLines => json_line(&current, &options.metrics),
+        };
+        // A closed pipe (e.g. `| head`) ends the run instead of panicking.
+        if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+            return;
+        }
+
+        printed += 1;
+        if options.count.is_some_and(|count| printed >= count) {
+            return;
+        }
+        std::thread::sleep(options.config.intervals.sample().saturating_sub(started.elapsed()));
+    }
+}
+
+const TIME_WIDTH: usize = 12;
+
+fn column_width(metric: Metric) -> usize {
+    (metric.key().len() + metric.unit().len() + 3).max(10)
+}
+
+fn table_header(metrics: &[Metric]) -> String {
+    let mut line = format!("{:<width$}", "time (UTC)", width = TIME_WIDTH);
+    for &metric in metrics {
+        let title = format!("{}[{}]", metric.key(), metric.unit());
+        line.push_str(&format!(" {:>width$}", title, width = column_width(metric)));
+    }
+    line
+}
+
+fn table_row(m: &HardwareMetrics, metrics: &[Metric]) -> String {
+    let mut line = format!("{:<width$}", utc_time_of_day(m.timestamp_secs()), width = TIME_WIDTH);
+    for &metric in metrics {
+        let width = column_width(metric);
+        if m.has_source(metric.source_id()) {
+            line.push_str(&format!(" {:>width$.2}", metric.value(m), width = width));
+        } else {
+            line.push_str(&format!(" {:>width$}", "n/a", width = width));
+        }
+    }
+    line
+}
+
+fn json_line(m: &HardwareMetrics, metrics: &[Metric]) -> String {
+    let mut object = serde_json::Map::new();
+    object.insert("timestamp".to_string(), serde_json::json!(m.timestamp_secs()));
+    for &metric in metrics {
+        // Unavailable metrics are null rather than a misleading 0.
+        let value = if m.has_source(metric.source_id()) {
+            serde_json::json!((metric.value(m) * 1000.0).round() / 1000.0)
+        } else {
+            serde_json::Value::Null
+        };
+        object.insert(metric.key().to_string(), value);
+    }
+    serde_json::Value::Object(object).to_string()
+}
+
+// HH:MM:SS.mmm, good enough to line samples up with other logs.
+fn utc_time_of_day(unix_secs: f64) -> String {
+    let millis = (unix_secs * 1000.0) as u64 % 86_400_000;
+    let secs = millis / 1000;
+    format!("{:02}:{:02}:{:02}.{:03}", secs / 3600, secs / 60 % 60, secs % 60, millis % 1000)
+}
@@ -0,0 +1,43 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use crate::metrics::{HardwareMetrics, Metric};
+
+// Rolling per-metric history. Each series is a ring buffer of
+// (unix seconds, value) pairs; anything older than `window` relative to the
+// newest sample is dropped on push.
+pub struct History {
+    window: Duration,
+    series: BTreeMap<Metric, VecDeque<[f64; 2]>>,
+}
+
+impl History {
+    pub fn new(window: Duration) -> Self {
+        Self { window, series: BTreeMap::new() }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn push(&mut self, m: &HardwareMetrics) {
+        let t = m.timestamp_secs();
+        let oldest = t - self.window.as_secs_f64();
+        for metric in Metric::ALL {
+            let series = self.series.entry(metric).or_default();
+            series.push_back([t, metric.value(m)]);
+            while series.front().is_some_and(|p| p[0] < oldest) {
+                series.pop_front();
+            }
+        }
+    }
+
+    // Samples for one metric, oldest first.
+    pub fn series(&self, metric: Metric) -> impl Iterator<Item = [f64; 2]> + '_ {
+        self.series.get(&metric).into_iter().flatten().copied()
+    }
+
+    pub fn latest_time(&self) -> Option<f64> {
+        self.series.values().filter_map(|s| s.back()).map(|p| p[0]).reduce(f64::max)
+    }
+}
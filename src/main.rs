@@ -1,43 +1,33 @@
 use eframe::egui;
-use sysinfo::System;
-use nvml_wrapper::Nvml;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-
-use windows_sys::Win32::System::Performance::{
-    self as pdh, PdhAddCounterW, PdhCollectQueryData, PdhGetFormattedCounterValue, PdhOpenQueryW,
-    PDH_FMT_COUNTERVALUE, PDH_FMT_DOUBLE,
-};
-
-struct HardwareMetrics {
-    cpu_usage: f32,
-    ram_used_gb: f32,
-    ram_total_gb: f32,
-    gpu_name: String,
-    gpu_pcie_tx: u64, // Bytes/s
-    gpu_pcie_rx: u64, // Bytes/s
-    gpu_vram_used_mb: u64,
-    gpu_vram_total_mb: u64,
-    disk_read_bps: u64,
-    disk_write_bps: u64,
-}
+use std::time::{Duration, SystemTime};
 
-impl Default for HardwareMetrics {
-    fn default() -> Self {
-        Self {
-            cpu_usage: 0.0,
-            ram_used_gb: 0.0,
-            ram_total_gb: 0.0,
-            gpu_name: "Detecting...".to_string(),
-            gpu_pcie_tx: 0,
-            gpu_pcie_rx: 0,
-            gpu_vram_used_mb: 0,
-            gpu_vram_total_mb: 0,
-            disk_read_bps: 0,
-            disk_write_bps: 0,
-        }
-    }
-}
+mod alerts;
+mod analysis;
+mod cli;
+mod config;
+mod exporter;
+mod headless;
+mod history;
+mod metrics;
+mod pcie;
+mod recording;
+mod replay;
+mod sources;
+#[cfg(all(test, target_os = "linux"))]
+mod test_util;
+mod topology;
+mod ui;
+
+use config::Config;
+use history::History;
+use metrics::HardwareMetrics;
+use recording::Recorder;
+use replay::Player;
+use sources::{Collector, SourceHealth};
 
 // --- Rust Memory Ownership & Threading Commentary ---
 // 1. Arc (Atomic Reference Counted): 
@@ -54,140 +44,380 @@ impl Default for HardwareMetrics {
 //    This borrow is short-lived; it ends when 'metrics' goes out of scope at the end of update().
 // ----------------------------------------------------
 
+// Where MonitorApp gets its samples from.
+enum Feed {
+    Live { collector: Collector, recorder: Option<Recorder> },
+    Replay(Player),
+}
+
 struct MonitorApp {
     metrics: Arc<Mutex<HardwareMetrics>>,
+    // Filled by the polling thread, so samples are kept even while the window
+    // is minimised and not repainting.
+    history: Arc<Mutex<History>>,
+    // In replay mode there is no polling thread; update() copies the player's
+    // current sample and history into the two fields above instead.
+    replay: Option<Player>,
+    // Sample range and window the replayed history was last built from, so
+    // it is only rebuilt when a sample enters or leaves it.
+    replayed: Option<(RangeInclusive<usize>, Duration)>,
+    disk_sort: ui::DiskSort,
+    heatmap_mode: ui::HeatmapMode,
+    process_view: ui::ProcessView,
+    // Path of the cgroup the main view is scoped to, if any.
+    pinned_cgroup: Option<String>,
+    // History of the pinned cgroup's view of each sample, filled by the
+    // polling thread from the moment it was pinned.
+    scoped_history: Arc<Mutex<Option<(String, History)>>>,
+    // Alert rules currently firing, maintained by the polling thread.
+    alerts: alerts::Banner,
+    // Scanned the first time the topology section is opened; the bus only
+    // changes on hotplug, so there's no point walking sysfs every frame.
+    topology: Option<Result<topology::Topology, String>>,
+    config: Config,
 }
 
 impl MonitorApp {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(_cc: &eframe::CreationContext<'_>, feed: Feed, options: &cli::Options) -> Self {
         let metrics = Arc::new(Mutex::new(HardwareMetrics::default()));
+        let history = Arc::new(Mutex::new(History::new(options.config.intervals.history())));
+        let interval = options.config.intervals.sample();
         
         // Clone the Arc. Cloning an Arc doesn't copy the data, just increments the reference count.
         // Both 'metrics' and 'metrics_clone' now point to the same memory on the heap.
         let metrics_clone = metrics.clone();
+        let history_clone = history.clone();
+        let scoped_history = Arc::new(Mutex::new(None));
+        let scoped_history_clone = Arc::clone(&scoped_history);
+        let banner = alerts::Banner::default();
+        let mut alerts = alerts::Alerts::new(&options.config.alerts, Some(banner.clone()));
+
+        if let Some(addr) = &options.config.exporter.listen {
+            if let Err(e) = exporter::spawn(addr, metrics.clone()) {
+                eprintln!("could not listen on {}: {}", addr, e);
+            }
+        }
+
+        let mut app = Self {
+            metrics,
+            history,
+            replay: None,
+            replayed: None,
+            disk_sort: ui::DiskSort::default(),
+            heatmap_mode: ui::HeatmapMode::default(),
+            process_view: ui::ProcessView::default(),
+            pinned_cgroup: None,
+            scoped_history,
+            alerts: banner,
+            topology: None,
+            config: options.config.clone(),
+        };
+        let (mut collector, mut recorder) = match feed {
+            Feed::Live { collector, recorder } => (collector, recorder),
+            Feed::Replay(player) => {
+                app.replay = Some(player);
+                return app;
+            }
+        };
 
         // Spawn a monitoring thread to poll hardware without blocking the GUI.
-        // 'move' moves the metrics_clone Arc into this thread's scope.
+        // 'move' moves the metrics_clone Arc (and the collector) into this thread's scope.
         std::thread::spawn(move || {
-            let mut sys = System::new_all();
-            let nvml = Nvml::init().ok();
-            
-            // Initialization for Windows Disk Counters (PDH)
-            let mut query: isize = 0;
-            let mut read_counter: isize = 0;
-            let mut write_counter: isize = 0;
-            
-            unsafe {
-                if pdh::PdhOpenQueryW(std::ptr::null(), 0, &mut query) == 0 {
-                    let read_path: Vec<u16> = r"\PhysicalDisk(_Total)\Disk Read Bytes/sec".encode_utf16().chain(Some(0)).collect();
-                    let write_path: Vec<u16> = r"\PhysicalDisk(_Total)\Disk Write Bytes/sec".encode_utf16().chain(Some(0)).collect();
-                    
-                    unsafe {
-                        pdh::PdhAddCounterW(query, read_path.as_ptr(), 0, &mut read_counter);
-                        pdh::PdhAddCounterW(query, write_path.as_ptr(), 0, &mut write_counter);
-                    }
-                }
-            }
+            collector.init();
 
+            // Sources write into a thread-local snapshot so the lock is only held
+            // for the copy, not while we wait on sysinfo/NVML/PDH.
+            let mut current = HardwareMetrics::default();
             loop {
-                sys.refresh_all();
-                
-                // Lock the mutex to get mutable access to the metrics.
-                // This blocks other threads (like the UI thread) until we drop 'm'.
-                let mut m = metrics_clone.lock().unwrap();
-                
-                // CPU & RAM usage (sysinfo)
-                m.cpu_usage = sys.global_cpu_usage();
-                m.ram_used_gb = sys.used_memory() as f32 / 1024.0 / 1024.0 / 1024.0;
-                m.ram_total_gb = sys.total_memory() as f32 / 1024.0 / 1024.0 / 1024.0;
-                
-                // GPU (nvml)
-                // Note: We track PCIe utilization here because NVML provides high-fidelity access 
-                // to NVIDIA-specific bus metrics. Standard Windows PDH counters usually don't 
-                // expose generic PCIe bus utilization for all devices in a unified way.
-                if let Some(ref n) = nvml {
-                    if let Ok(device) = n.device_by_index(0) {
-                        m.gpu_name = device.name().unwrap_or_else(|_| "Unknown GPU".to_string());
-                        if let Ok(pcie) = device.pcie_throughput(nvml_wrapper::enum_wrappers::device::PcieUtilCounter::Send) {
-                            m.gpu_pcie_tx = pcie as u64 * 1024; // reported in KB/s
-                        }
-                        if let Ok(pcie) = device.pcie_throughput(nvml_wrapper::enum_wrappers::device::PcieUtilCounter::Receive) {
-                            m.gpu_pcie_rx = pcie as u64 * 1024;
-                        }
-                        if let Ok(mem) = device.memory_info() {
-                            m.gpu_vram_used_mb = mem.used / 1024 / 1024;
-                            m.gpu_vram_total_mb = mem.total / 1024 / 1024;
-                        }
+                current.timestamp = SystemTime::now();
+                collector.sample(&mut current);
+                current.sources = collector.statuses();
+                history_clone.lock().unwrap().push(&current);
+                if let Some((path, history)) = &mut *scoped_history_clone.lock().unwrap() {
+                    // A cgroup that went away leaves a gap until it comes back.
+                    if let Some(scoped) = current.scoped_to(path) {
+                        history.push(&scoped);
                     }
                 }
-                
-                // Disk metrics using PDH
-                unsafe {
-                    if query != 0 && pdh::PdhCollectQueryData(query) == 0 {
-                        let mut read_value: PDH_FMT_COUNTERVALUE = std::mem::zeroed();
-                        if pdh::PdhGetFormattedCounterValue(read_counter, PDH_FMT_DOUBLE, std::ptr::null_mut(), &mut read_value) == 0 {
-                            m.disk_read_bps = read_value.Anonymous.doubleValue as u64;
-                        }
-                        let mut write_value: PDH_FMT_COUNTERVALUE = std::mem::zeroed();
-                        if pdh::PdhGetFormattedCounterValue(write_counter, PDH_FMT_DOUBLE, std::ptr::null_mut(), &mut write_value) == 0 {
-                            m.disk_write_bps = write_value.Anonymous.doubleValue as u64;
-                        }
-                    }
+                alerts.evaluate(&current);
+                if let Some(Err(e)) = recorder.as_mut().map(|r| r.record(&current)) {
+                    eprintln!("recording stopped: {}", e);
+                    recorder = None;
                 }
-                
-                // Mutex guard 'm' is dropped here, releasing the lock.
-                drop(m); 
-                std::thread::sleep(Duration::from_millis(500));
+
+                // Lock the mutex to get mutable access to the metrics.
+                // This blocks other threads (like the UI thread) until the guard is dropped
+                // at the end of the statement.
+                *metrics_clone.lock().unwrap() = current.clone();
+
+                std::thread::sleep(interval);
             }
         });
 
-        Self { metrics }
+        app
+    }
+
+    // Starts a fresh scoped history when the pin changes. Earlier samples
+    // can't be scoped after the fact: cgroups aren't kept in the history.
+    fn follow_pin(&self) {
+        let mut scoped = self.scoped_history.lock().unwrap();
+        if scoped.as_ref().map(|(path, _)| path) != self.pinned_cgroup.as_ref() {
+            let window = self.history.lock().unwrap().window();
+            *scoped = self.pinned_cgroup.clone().map(|path| (path, History::new(window)));
+        }
     }
 }
 
 impl eframe::App for MonitorApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let mut repaint_after = self.config.intervals.repaint();
+        let layout = &self.config.layout;
+        let units = self.config.units.bandwidth;
+        if let Some(player) = &mut self.replay {
+            egui::TopBottomPanel::top("replay_panel").show(ctx, |ui| ui::replay_controls(ui, player));
+            player.tick();
+            let window = self.history.lock().unwrap().window();
+            let replayed = Some((player.history_range(window), window));
+            if self.replayed != replayed {
+                *self.history.lock().unwrap() = player.history(window);
+                *self.metrics.lock().unwrap() = player.current().clone();
+                self.replayed = replayed;
+            }
+            if player.playing {
+                // Keep the timeline moving smoothly, whatever the recorded interval was.
+                repaint_after = repaint_after.min(Duration::from_millis(50));
+            }
+        }
+
+        self.follow_pin();
+
         // Here we borrow the metrics data for the duration of this function.
         // Rust ensures that while we have this lock, no other thread can mutate the data.
         let metrics = self.metrics.lock().unwrap();
         
+        let firing = self.alerts.lock().unwrap().clone();
+        if !firing.is_empty() {
+            egui::TopBottomPanel::top("alerts_panel").show(ctx, |ui| ui::alert_banner(ui, &firing));
+        }
+
+        // Until the first sample arrives we don't know which backends exist yet.
+        let detecting = metrics.sources.is_empty();
+
+        // A pinned cgroup scopes everything the kernel accounts per cgroup,
+        // history and analysis included; GPU, network and power stay host-wide.
+        let scoped = self.pinned_cgroup.as_deref().and_then(|path| metrics.scoped_to(path));
+        let metrics: &HardwareMetrics = scoped.as_ref().unwrap_or(&metrics);
+        let host_history = self.history.lock().unwrap();
+        let scoped_history = self.scoped_history.lock().unwrap();
+        let history: &History = match (&scoped, &*scoped_history) {
+            (Some(_), Some((_, history))) => history,
+            _ => &host_history,
+        };
+
+        if layout.show_history {
+            egui::SidePanel::right("history_panel").default_width(380.0).show(ctx, |ui| {
+                let secs = history.window().as_secs();
+                if secs >= 60 {
+                    ui.heading(format!("History (last {} min)", secs / 60));
+                } else {
+                    ui.heading(format!("History (last {} s)", secs));
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| ui::history_plots(ui, history, metrics, units));
+            });
+        }
+
+        let verdict = analysis::analyze(history, metrics, &self.config.thresholds);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Hardware Bandwidth Monitor");
+
+            if let Some(path) = self.pinned_cgroup.clone() {
+                ui.horizontal_wrapped(|ui| {
+                    if scoped.is_some() {
+                        ui.strong(format!("Scoped to cgroup {}", path));
+                        ui.weak("(CPU, memory, disk I/O, processes, history and analysis since pinning; GPU, network and power are host-wide)");
+                    } else {
+                        ui.strong(format!("cgroup {} not found, showing the whole host", path));
+                    }
+                    if ui.small_button("Unpin").clicked() {
+                        self.pinned_cgroup = None;
+                    }
+                });
+            }
             
             ui.separator();
             
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
                     ui.label(format!("CPU Usage: {:.1}%", metrics.cpu_usage));
+                    if let Some(t) = &metrics.cpu_times {
+                        ui.weak(format!(
+                            "usr {:.1}  nice {:.1}  sys {:.1}  iowait {:.1}  irq {:.1}  soft {:.1}  steal {:.1}",
+                            t.user, t.nice, t.system, t.iowait, t.irq, t.softirq, t.steal
+                        ));
+                    }
                     ui.label(format!("RAM: {:.1}/{:.1} GB", metrics.ram_used_gb, metrics.ram_total_gb));
+                    if let Some(mem) = &metrics.memory {
+                        let gb = |bytes: u64| bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+                        ui.weak(format!(
+                            "avail {:.1}  cached {:.1}  buffers {:.2}  dirty {:.0} MB  swap {:.1}/{:.1} GB",
+                            gb(mem.available_bytes),
+                            gb(mem.cached_bytes),
+                            gb(mem.buffers_bytes),
+                            mem.dirty_bytes as f64 / 1024.0 / 1024.0,
+                            gb(mem.swap_used_bytes),
+                            gb(mem.swap_total_bytes)
+                        ));
+                    }
+                    ui::pressure_summary(ui, &metrics.psi);
+                    ui::power_summary(ui, metrics);
                 });
                 
                 ui.add_space(20.0);
                 
                 ui.vertical(|ui| {
                     ui.set_max_width(360.0);
-                    ui.label(format!("GPU: {}", metrics.gpu_name));
-                    ui.label(format!("VRAM: {}/{} MB", metrics.gpu_vram_used_mb, metrics.gpu_vram_total_mb));
-                    ui.label(format!("PCIe TX (Send): {:.2} MB/s", metrics.gpu_pcie_tx as f32 / 1024.0 / 1024.0));
-                    ui.label(format!("PCIe RX (Receive): {:.2} MB/s", metrics.gpu_pcie_rx as f32 / 1024.0 / 1024.0));
+                    if detecting || metrics.has_source("gpu") {
+                        // With several cards this is the aggregate row; the cards follow below.
+                        ui.label(format!("GPU: {}", metrics.gpu_name));
+                        ui.label(format!("VRAM: {}/{} MB", metrics.gpu_vram_used_mb, metrics.gpu_vram_total_mb));
+                        if metrics.gpus.iter().any(|g| g.util_pct.is_some()) {
+                            ui.label(format!("Utilization: {:.0}%", metrics.gpu_util_pct));
+                        }
+                        ui.label(format!("PCIe TX (Send): {}", units.format(metrics.gpu_pcie_tx)));
+                        ui.label(format!("PCIe RX (Receive): {}", units.format(metrics.gpu_pcie_rx)));
+                    } else {
+                        ui.label("GPU: unavailable");
+                    }
                 });
             });
             
+            if !metrics.gpus.is_empty() && metrics.has_source("gpu") {
+                ui.add_space(6.0);
+                ui::gpu_cards(ui, &metrics.gpus, units);
+            }
+
+            if layout.show_cores && !metrics.cores.is_empty() {
+                ui.add_space(6.0);
+                egui::CollapsingHeader::new(format!("Per-core ({} logical CPUs)", metrics.cores.len()))
+                    .id_salt("cores")
+                    .default_open(true)
+                    .show(ui, |ui| ui::core_heatmap(ui, &metrics.cores, &mut self.heatmap_mode));
+            }
+
             ui.separator();
             ui.heading("Storage Bandwidth (Total Disk I/O)");
-            ui.label(format!("Global Read: {:.2} MB/s", metrics.disk_read_bps as f32 / 1024.0 / 1024.0));
-            ui.label(format!("Global Write: {:.2} MB/s", metrics.disk_write_bps as f32 / 1024.0 / 1024.0));
+            if detecting || metrics.has_source("disk") {
+                ui.label(format!("Global Read: {}", units.format(metrics.disk_read_bps)));
+                ui.label(format!("Global Write: {}", units.format(metrics.disk_write_bps)));
+                if layout.show_disk_table && !metrics.disks.is_empty() {
+                    ui.add_space(6.0);
+                    ui::disk_table(ui, &metrics.disks, &mut self.disk_sort, units);
+                }
+                if layout.show_nvme_health && metrics.has_source("nvme") {
+                    ui.add_space(6.0);
+                    egui::CollapsingHeader::new("NVMe Health").id_salt("nvme_health").show(ui, |ui| ui::nvme_health(ui, &metrics.nvme));
+                }
+            } else {
+                ui.label("Disk bandwidth: unavailable");
+            }
+
+            ui.separator();
+            ui.heading("Network Bandwidth");
+            if detecting || metrics.has_source("net") {
+                ui.label(format!("Global RX: {}", units.format(metrics.net_rx_bps)));
+                ui.label(format!("Global TX: {}", units.format(metrics.net_tx_bps)));
+                if layout.show_network_table && !metrics.interfaces.is_empty() {
+                    ui.add_space(6.0);
+                    ui::network_table(ui, &metrics.interfaces, units);
+                }
+            } else {
+                ui.label("Network bandwidth: unavailable");
+            }
+
+            // Processes aren't recorded, so there's nothing to show in a replay.
+            if layout.show_processes && metrics.has_source("procs") && self.replay.is_none() {
+                ui.separator();
+                egui::CollapsingHeader::new(format!("Top Processes ({})", metrics.processes.len()))
+                    .id_salt("processes")
+                    .default_open(true)
+                    .show(ui, |ui| ui::process_table(ui, &metrics.processes, &mut self.process_view, units));
+            }
             
-            ui.add_space(10.0);
-            ui.weak("Monitor detects bottlenecks in data movement between NVMe, RAM, and GPU.");
+            // Neither are cgroups.
+            if layout.show_cgroups && metrics.has_source("cgroup") && self.replay.is_none() {
+                ui.separator();
+                egui::CollapsingHeader::new(format!("cgroups ({})", metrics.cgroups.len()))
+                    .id_salt("cgroups")
+                    .show(ui, |ui| ui::cgroup_tree(ui, &metrics.cgroups, &mut self.pinned_cgroup, units));
+            }
+
+            if layout.show_sensors && metrics.has_source("sensors") {
+                ui.separator();
+                egui::CollapsingHeader::new("Sensors").id_salt("sensors").show(ui, |ui| ui::sensor_panel(ui, &metrics.sensors));
+            }
+
+            if layout.show_topology {
+                ui.separator();
+                egui::CollapsingHeader::new("PCIe Topology").id_salt("topology").show(ui, |ui| {
+                    if ui.button("Rescan").clicked() {
+                        self.topology = None;
+                    }
+                    let tree = self.topology.get_or_insert_with(|| {
+                        topology::Topology::scan(Path::new(pcie::SYS_PCI_DEVICES), Path::new(topology::SYS_CLASS_NVME))
+                    });
+                    match tree {
+                        Ok(tree) => {
+                            let mut highlights = HashMap::new();
+                            for gpu in metrics.gpus.iter().filter(|g| !g.pci_bus_id.is_empty()) {
+                                highlights.insert(pcie::sysfs_address(&gpu.pci_bus_id), format!("GPU {}", gpu.index));
+                            }
+                            for disk in &metrics.disks {
+                                if let Some(address) = tree.nvme_address(&disk.name) {
+                                    // Several namespaces can sit behind one controller.
+                                    highlights
+                                        .entry(address.to_string())
+                                        .and_modify(|tag: &mut String| *tag = format!("{}, {}", tag, disk.name))
+                                        .or_insert_with(|| disk.name.clone());
+                                }
+                            }
+                            ui::topology_tree(ui, &tree.roots, &highlights);
+                        }
+                        Err(e) => {
+                            ui.label(format!("PCI topology unavailable: {}", e));
+                        }
+                    }
+                });
+            }
+
+            if layout.show_analysis {
+                ui.separator();
+                ui.heading("Bottleneck Analysis");
+                ui::verdict_panel(ui, &verdict);
+                ui.weak("Monitor detects bottlenecks in data movement between NVMe, RAM, and GPU.");
+            }
+
+            ui.separator();
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Sources:");
+                for status in &metrics.sources {
+                    let state = match (&status.health, status.enabled) {
+                        (_, false) => "disabled".to_string(),
+                        (SourceHealth::Pending, _) => "starting".to_string(),
+                        (SourceHealth::Ok, _) => "ok".to_string(),
+                        (SourceHealth::Unavailable(e), _) => format!("unavailable: {}", e),
+                        (SourceHealth::Degraded(e), _) => format!("degraded: {}", e),
+                    };
+                    ui.weak(format!("{} [{}]", status.description, state));
+                }
+            });
         });
 
-        ctx.request_repaint_after(Duration::from_millis(500));
+        ctx.request_repaint_after(repaint_after);
     }
 }
 
-fn load_icon() -> Option<egui::IconData> {
-    let icon_path = "assets/favicon.ico";
+fn load_icon(icon_path: &std::path::Path) -> Option<egui::IconData> {
     if let Ok(image) = image::open(icon_path) {
         let image = image.to_rgba8();
         let (width, height) = image.dimensions();
@@ -199,16 +429,57 @@ fn load_icon() -> Option<egui::IconData> {
 }
 
 fn main() -> eframe::Result {
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    let collector = Collector::with_default_sources(&options.config.sources);
+
+    let recorder = options.record.as_ref().map(|path| match Recorder::create(path) {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            eprintln!("error: cannot record: {}", e);
+            std::process::exit(1);
+        }
+    });
+
+    if options.headless {
+        headless::run(collector, recorder, &options);
+        return Ok(());
+    }
+
+    let feed = match &options.replay {
+        Some(path) => match recording::load(path).and_then(Player::new) {
+            Ok(player) => Feed::Replay(player),
+            Err(e) => {
+                eprintln!("error: cannot replay: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => Feed::Live { collector, recorder },
+    };
+
     let mut native_options = eframe::NativeOptions::default();
     
     // Set the favicon if available
-    if let Some(icon) = load_icon() {
+    if let Some(icon) = load_icon(&options.config.layout.icon) {
         native_options.viewport.icon = Some(Arc::new(icon));
     }
+    if let Some(size) = options.config.layout.window_size {
+        native_options.viewport.inner_size = Some(size.into());
+    }
 
     eframe::run_native(
         "Bandwidth Monitor",
         native_options,
-        Box::new(|cc| Ok(Box::new(MonitorApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(MonitorApp::new(cc, feed, &options)))),
     )
 }
@@ -0,0 +1,862 @@
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+use crate::sources::{SourceHealth, SourceStatus};
+
+// Snapshot of everything the collector knows about the machine.
+// Each MetricSource fills in the fields it owns; fields belonging to a
+// missing or disabled source keep their defaults.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HardwareMetrics {
+    // Wall-clock time the sample was taken, set by the polling loop.
+    pub timestamp: SystemTime,
+    pub cpu_usage: f32,
+    pub ram_used_gb: f32,
+    pub ram_total_gb: f32,
+    // Finer CPU and memory breakdowns, only filled in by sources that can
+    // provide them (the procfs source on Linux).
+    pub cpu_times: Option<CpuTimes>,
+    pub memory: Option<MemoryDetail>,
+    // Pressure stall information, Linux only.
+    pub psi: Psi,
+    // One entry per logical CPU, ordered by CPU number.
+    pub cores: Vec<CoreMetrics>,
+    // The gpu_* fields are the aggregate over `gpus`: the card's name (or
+    // "N GPUs") and the summed memory and PCIe traffic.
+    pub gpu_name: String,
+    pub gpu_pcie_tx: u64, // Bytes/s
+    pub gpu_pcie_rx: u64, // Bytes/s
+    pub gpu_vram_used_mb: u64,
+    pub gpu_vram_total_mb: u64,
+    // Mean utilization over the GPUs that report it.
+    pub gpu_util_pct: f32,
+    pub gpus: Vec<GpuMetrics>,
+    pub disk_read_bps: u64,
+    pub disk_write_bps: u64,
+    // Per physical block device; the totals above stay the sum over these.
+    pub disks: Vec<DiskDeviceMetrics>,
+    pub net_rx_bps: u64,
+    pub net_tx_bps: u64,
+    // Same relationship as disks: the totals are the sum over these.
+    pub interfaces: Vec<NetInterfaceMetrics>,
+    // Every process alive at both ends of the sample interval, unsorted.
+    // Live only: a row per process on every sample would make up most of a
+    // recording, so it isn't recorded.
+    #[serde(skip)]
+    pub processes: Vec<ProcessMetrics>,
+    // Every cgroup below the root, parents before children. Live only, like
+    // `processes`.
+    #[serde(skip)]
+    pub cgroups: Vec<CgroupMetrics>,
+    // Hardware monitoring chips (k10temp, nvme, amdgpu, ...) with their readings.
+    pub sensors: Vec<SensorChip>,
+    // RAPL energy domains (package, core, uncore, dram) of every socket.
+    pub power: Vec<PowerDomain>,
+    // One entry per NVMe controller, by name.
+    pub nvme: Vec<NvmeController>,
+    pub sources: Vec<SourceStatus>,
+}
+
+// Share of all CPU time spent in each mode since the previous sample, in %.
+// Idle is whatever is left over.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CpuTimes {
+    pub user: f32,
+    pub nice: f32,
+    pub system: f32,
+    pub iowait: f32,
+    pub irq: f32,
+    pub softirq: f32,
+    pub steal: f32,
+}
+
+impl CpuTimes {
+    // Everything but idle and iowait, which is what "CPU usage" usually means.
+    pub fn busy(&self) -> f32 {
+        self.user + self.nice + self.system + self.irq + self.softirq + self.steal
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CoreMetrics {
+    // Logical CPU number as the OS counts it.
+    pub cpu: u32,
+    // Physical core and package this logical CPU belongs to. SMT siblings
+    // share both; without topology information every CPU is its own core.
+    pub core_id: u32,
+    pub package_id: u32,
+    pub usage: f32,
+    pub freq_mhz: u32,
+    // 0 when unknown.
+    pub max_freq_mhz: u32,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MemoryDetail {
+    pub total_bytes: u64,
+    // What the kernel estimates can be allocated without swapping.
+    pub available_bytes: u64,
+    pub cached_bytes: u64,
+    pub buffers_bytes: u64,
+    // Written to but not yet flushed to disk.
+    pub dirty_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_used_bytes: u64,
+}
+
+// Fields a backend can't read for a card stay at their defaults; the
+// optional ones are None so the UI can tell "unsupported" from zero.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpuMetrics {
+    // Backend device index (NVML index for NVIDIA cards).
+    pub index: u32,
+    pub name: String,
+    pub uuid: String,
+    pub pci_bus_id: String,
+    pub vram_used_mb: u64,
+    pub vram_total_mb: u64,
+    pub pcie_tx: u64, // Bytes/s
+    pub pcie_rx: u64, // Bytes/s
+    pub util_pct: Option<u32>,     // share of time a kernel was running
+    pub mem_util_pct: Option<u32>, // share of time device memory was being read or written
+    pub graphics_clock_mhz: Option<u32>,
+    pub memory_clock_mhz: Option<u32>,
+    pub max_graphics_clock_mhz: Option<u32>,
+    pub max_memory_clock_mhz: Option<u32>,
+    pub temperature_c: Option<u32>,
+    pub power_w: Option<f32>,
+    pub power_limit_w: Option<f32>,
+    pub pcie_link: Option<PcieLink>,
+    // Why clocks are currently held below their maximum; empty if they aren't.
+    pub throttle_reasons: Vec<ThrottleReason>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PcieLink {
+    // Negotiated generation and lane count, and what the device supports.
+    // Idle GPUs drop to a lower generation to save power, so a low current
+    // generation alone is not a fault.
+    pub gen: u32,
+    pub width: u32,
+    pub max_gen: u32,
+    pub max_width: u32,
+}
+
+impl PcieLink {
+    // Per-direction ceiling of the negotiated link in bytes/s.
+    pub fn bandwidth_bps(&self) -> Option<f64> {
+        crate::pcie::link_bytes_per_sec(self.gen, self.width)
+    }
+
+    // Fewer lanes than the device supports: a x16 card in a x8 slot or with
+    // a bad riser. Unlike the generation this doesn't change with load.
+    pub fn narrower_than_max(&self) -> bool {
+        self.width < self.max_width
+    }
+
+    pub fn slower_than_max(&self) -> bool {
+        self.gen < self.max_gen
+    }
+}
+
+impl GpuMetrics {
+    // Busier direction as a share of the negotiated link, None if the link is unknown.
+    pub fn pcie_util_pct(&self) -> Option<f32> {
+        let ceiling = self.pcie_link?.bandwidth_bps()?;
+        Some((self.pcie_tx.max(self.pcie_rx) as f64 / ceiling * 100.0) as f32)
+    }
+}
+
+// Decoded NVML clock throttle reasons.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ThrottleReason {
+    Idle,
+    ApplicationClocks,
+    SwPowerCap,
+    HwSlowdown,
+    SyncBoost,
+    SwThermal,
+    HwThermal,
+    HwPowerBrake,
+    DisplayClocks,
+}
+
+impl ThrottleReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            ThrottleReason::Idle => "idle",
+            ThrottleReason::ApplicationClocks => "application clocks",
+            ThrottleReason::SwPowerCap => "power cap",
+            ThrottleReason::HwSlowdown => "hardware slowdown",
+            ThrottleReason::SyncBoost => "sync boost",
+            ThrottleReason::SwThermal => "thermal (software)",
+            ThrottleReason::HwThermal => "thermal (hardware)",
+            ThrottleReason::HwPowerBrake => "power brake",
+            ThrottleReason::DisplayClocks => "display clocks",
+        }
+    }
+
+    // Reasons that cost performance under load, as opposed to the GPU simply
+    // having nothing to do or following a configured clock.
+    pub fn is_limiting(self) -> bool {
+        matches!(
+            self,
+            ThrottleReason::SwPowerCap
+                | ThrottleReason::HwSlowdown
+                | ThrottleReason::SwThermal
+                | ThrottleReason::HwThermal
+                | ThrottleReason::HwPowerBrake
+        )
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DiskDeviceMetrics {
+    pub name: String,
+    pub read_bps: u64,
+    pub write_bps: u64,
+    pub read_iops: f32,
+    pub write_iops: f32,
+    pub avg_latency_ms: f32, // mean time per completed I/O
+    pub queue_depth: f32,    // average number of I/Os in flight
+    pub busy_pct: f32,       // share of wall time with at least one I/O in flight
+    // Running totals, for exporters that want counters rather than rates.
+    pub read_bytes_total: u64,
+    pub write_bytes_total: u64,
+    pub reads_total: u64,
+    pub writes_total: u64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct NetInterfaceMetrics {
+    pub name: String,
+    pub rx_bps: u64,
+    pub tx_bps: u64,
+    pub rx_pps: f32,
+    pub tx_pps: f32,
+    // Negotiated speed in Mb/s, 0 when unknown or the link is down.
+    pub link_speed_mbps: u32,
+    pub rx_bytes_total: u64,
+    pub tx_bytes_total: u64,
+    pub rx_packets_total: u64,
+    pub tx_packets_total: u64,
+    pub rx_errors_total: u64,
+    pub tx_errors_total: u64,
+    pub rx_drops_total: u64,
+    pub tx_drops_total: u64,
+}
+
+impl NetInterfaceMetrics {
+    // Busier direction as a share of the link, None if the speed is unknown.
+    pub fn utilization_pct(&self) -> Option<f32> {
+        (self.link_speed_mbps > 0)
+            .then(|| self.rx_bps.max(self.tx_bps) as f32 * 8.0 / (self.link_speed_mbps as f32 * 1e6) * 100.0)
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ProcessMetrics {
+    pub pid: u32,
+    pub name: String,
+    // Login name, or the numeric uid if it has none.
+    pub user: String,
+    // Share of one CPU, so a multi-threaded process can go above 100.
+    pub cpu_pct: f32,
+    pub rss_bytes: u64,
+    // Bytes actually read from and written to storage, not the page cache.
+    // None where the kernel won't say (other users' processes without root).
+    pub read_bps: Option<u64>,
+    pub write_bps: Option<u64>,
+    // cgroup v2 path, e.g. "/system.slice/docker-1f2e.scope". Empty where
+    // there is none (other platforms, cgroup v1 hosts).
+    pub cgroup: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CgroupMetrics {
+    // Relative to the hierarchy root, e.g. "/user.slice/user-1000.slice".
+    pub path: String,
+    // Share of one CPU, like ProcessMetrics::cpu_pct.
+    pub cpu_pct: f32,
+    pub memory_bytes: u64,
+    // None when unlimited.
+    pub memory_max_bytes: Option<u64>,
+    // Sum over `io`.
+    pub read_bps: u64,
+    pub write_bps: u64,
+    pub io: Vec<CgroupIoMetrics>,
+    pub psi: Psi,
+}
+
+impl CgroupMetrics {
+    // Last path component, "/" for the root.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or("/")
+    }
+
+    // Path of the parent cgroup, None for top-level ones.
+    pub fn parent(&self) -> Option<&str> {
+        self.path.rsplit_once('/').map(|(parent, _)| parent).filter(|p| !p.is_empty())
+    }
+
+    // True for this cgroup and everything below it.
+    pub fn contains(&self, path: &str) -> bool {
+        path == self.path || path.strip_prefix(self.path.as_str()).is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SensorKind {
+    Temperature,
+    Fan,
+    Voltage,
+    Power,
+}
+
+impl SensorKind {
+    pub fn unit(self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "°C",
+            SensorKind::Fan => "RPM",
+            SensorKind::Voltage => "V",
+            SensorKind::Power => "W",
+        }
+    }
+}
+
+// One sensor of a chip, in the unit of its kind.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SensorReading {
+    pub kind: SensorKind,
+    // The chip's label ("Tctl", "Composite", "Package id 0"), or the sysfs
+    // name ("temp1") if it has none.
+    pub label: String,
+    pub value: f64,
+    // Thresholds as reported by the driver; most sensors lack one or both.
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+impl SensorReading {
+    pub fn is_critical(&self) -> bool {
+        self.crit.is_some_and(|crit| self.value >= crit)
+    }
+
+    pub fn is_over_max(&self) -> bool {
+        self.max.is_some_and(|max| self.value >= max)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SensorChip {
+    // Driver name from the chip's `name` file: "k10temp", "coretemp", "nvme", ...
+    pub name: String,
+    // The device the chip belongs to, e.g. "0000:01:00.0" or "nvme0", to tell
+    // apart several chips of the same driver. Empty for platform devices
+    // without one.
+    pub device: String,
+    pub sensors: Vec<SensorReading>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct PowerDomain {
+    // powercap zone, e.g. "intel-rapl:0:2".
+    pub zone: String,
+    // The zone's name: "package-0", "core", "uncore", "dram" or "psys".
+    pub name: String,
+    // Package the domain belongs to, "package-0" for itself and its subzones.
+    pub package: String,
+    // Mean power since the previous sample.
+    pub watts: f32,
+    // Energy used since the source started, with counter wraparound undone.
+    pub energy_joules: f64,
+}
+
+impl PowerDomain {
+    // "package" for "package-0", the name itself for the others.
+    pub fn kind(&self) -> &str {
+        self.name.split('-').next().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NvmeController {
+    // "nvme0".
+    pub name: String,
+    pub model: String,
+    pub serial: String,
+    pub firmware: String,
+    // "pcie", "tcp", "rdma", "fc" or "loop".
+    pub transport: String,
+    // PCI address for PCIe, the target address for fabrics.
+    pub address: String,
+    // "live" while usable; "resetting", "connecting", "dead" and so on otherwise.
+    pub state: String,
+    // Composite temperature from the controller's hwmon chip, in °C.
+    pub temperature_c: Option<f64>,
+    pub temperature_crit_c: Option<f64>,
+    // The SMART / health log, or why it couldn't be read.
+    pub smart: Result<SmartLog, String>,
+}
+
+// The fields of the SMART / Health Information log page (02h) worth showing.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SmartLog {
+    // Bit field, see `warnings`.
+    pub critical_warning: u8,
+    pub temperature_c: f32,
+    pub available_spare_pct: u8,
+    pub available_spare_threshold_pct: u8,
+    // Share of the rated endurance used up; may exceed 100.
+    pub percentage_used: u8,
+    pub data_read_bytes: u64,
+    pub data_written_bytes: u64,
+    pub power_on_hours: u64,
+    pub unsafe_shutdowns: u64,
+    pub media_errors: u64,
+    pub error_log_entries: u64,
+}
+
+impl SmartLog {
+    // The critical warning bits that are set, as short descriptions.
+    pub fn warnings(&self) -> Vec<&'static str> {
+        const BITS: [&str; 6] = [
+            "spare below threshold",
+            "temperature",
+            "reliability degraded",
+            "read-only",
+            "volatile backup failed",
+            "persistent memory read-only",
+        ];
+        BITS.iter().enumerate().filter(|(bit, _)| self.critical_warning & (1 << bit) != 0).map(|(_, w)| *w).collect()
+    }
+
+    pub fn spare_low(&self) -> bool {
+        self.available_spare_pct <= self.available_spare_threshold_pct
+    }
+}
+
+// One line of a PSI file: the share of wall time in which tasks were stalled
+// waiting for the resource, in % averaged over 10 s, 60 s and 300 s, plus the
+// total stall time since boot.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PressureLine {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+    pub total_usec: u64,
+}
+
+// "some" counts time in which at least one task was stalled, "full" time in
+// which all of them were at once, i.e. the machine got no work done. CPU has
+// no meaningful "full" outside cgroups; older kernels leave it out.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Pressure {
+    pub some: PressureLine,
+    pub full: Option<PressureLine>,
+}
+
+// None for resources the kernel doesn't report pressure for.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Psi {
+    pub cpu: Option<Pressure>,
+    pub memory: Option<Pressure>,
+    pub io: Option<Pressure>,
+}
+
+impl Psi {
+    pub fn is_empty(&self) -> bool {
+        self.cpu.is_none() && self.memory.is_none() && self.io.is_none()
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CgroupIoMetrics {
+    // Block device name, e.g. "nvme0n1", or "major:minor" if sysfs doesn't know it.
+    pub device: String,
+    pub read_bps: u64,
+    pub write_bps: u64,
+}
+
+impl Default for HardwareMetrics {
+    fn default() -> Self {
+        Self {
+            timestamp: SystemTime::UNIX_EPOCH,
+            cpu_usage: 0.0,
+            ram_used_gb: 0.0,
+            ram_total_gb: 0.0,
+            cpu_times: None,
+            memory: None,
+            psi: Psi::default(),
+            cores: Vec::new(),
+            gpu_name: "Detecting...".to_string(),
+            gpu_pcie_tx: 0,
+            gpu_pcie_rx: 0,
+            gpu_vram_used_mb: 0,
+            gpu_vram_total_mb: 0,
+            gpu_util_pct: 0.0,
+            gpus: Vec::new(),
+            disk_read_bps: 0,
+            disk_write_bps: 0,
+            disks: Vec::new(),
+            net_rx_bps: 0,
+            net_tx_bps: 0,
+            interfaces: Vec::new(),
+            processes: Vec::new(),
+            cgroups: Vec::new(),
+            sensors: Vec::new(),
+            power: Vec::new(),
+            nvme: Vec::new(),
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl HardwareMetrics {
+    // True if an enabled source with this id is registered and initialised.
+    // Sources that are compiled out, disabled or failed to start report false,
+    // so the UI can say "unavailable" instead of showing stale defaults.
+    pub fn has_source(&self, id: &str) -> bool {
+        self.sources.iter().any(|s| {
+            s.id == id && s.enabled && matches!(s.health, SourceHealth::Ok | SourceHealth::Degraded(_))
+        })
+    }
+
+    // The sample as seen from inside one cgroup: CPU, memory, disk I/O and
+    // processes are the cgroup's own, GPU and network stay host-wide because
+    // the kernel doesn't account them per cgroup. None if the cgroup is gone.
+    pub fn scoped_to(&self, path: &str) -> Option<HardwareMetrics> {
+        let cgroup = self.cgroups.iter().find(|c| c.path == path)?;
+        let gb = |bytes: u64| bytes as f32 / 1024.0 / 1024.0 / 1024.0;
+        let mut m = self.clone();
+        m.cpu_usage = (cgroup.cpu_pct / self.cores.len().max(1) as f32).min(100.0);
+        m.cpu_times = None;
+        m.ram_used_gb = gb(cgroup.memory_bytes);
+        // A limit above the machine's RAM is no limit at all.
+        if let Some(max) = cgroup.memory_max_bytes {
+            m.ram_total_gb = m.ram_total_gb.min(gb(max));
+        }
+        m.memory = None;
+        m.psi = cgroup.psi.clone();
+        m.disk_read_bps = cgroup.read_bps;
+        m.disk_write_bps = cgroup.write_bps;
+        // Bytes are the cgroup's, IOPS, latency and busy time the whole device's.
+        m.disks = self
+            .disks
+            .iter()
+            .filter_map(|disk| {
+                let io = cgroup.io.iter().find(|io| io.device == disk.name)?;
+                Some(DiskDeviceMetrics { read_bps: io.read_bps, write_bps: io.write_bps, ..disk.clone() })
+            })
+            .collect();
+        m.processes.retain(|p| cgroup.contains(&p.cgroup));
+        Some(m)
+    }
+
+    // Summed over sockets, e.g. every "package-N" for "package".
+    pub fn power_watts(&self, kind: &str) -> f64 {
+        self.power.iter().filter(|d| d.kind() == kind).map(|d| d.watts as f64).sum()
+    }
+
+    // Bytes through disks, PCIe and network per joule the packages and DRAM
+    // used, None without RAPL. Package power already includes core and uncore.
+    pub fn bytes_per_joule(&self) -> Option<f64> {
+        let watts = self.power_watts("package") + self.power_watts("dram");
+        if watts <= 0.0 {
+            return None;
+        }
+        let moved = self.disk_read_bps
+            + self.disk_write_bps
+            + self.gpu_pcie_tx
+            + self.gpu_pcie_rx
+            + self.net_rx_bps
+            + self.net_tx_bps;
+        Some(moved as f64 / watts)
+    }
+
+    // Seconds since the Unix epoch, the time axis used by history and plots.
+    pub fn timestamp_secs(&self) -> f64 {
+        self.timestamp.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0)
+    }
+}
+
+// The scalar metrics that can be plotted, recorded and exported, each in the
+// unit the UI shows it in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Metric {
+    CpuUsage,
+    RamUsed,
+    VramUsed,
+    VramPct,
+    GpuUtil,
+    PcieTx,
+    PcieRx,
+    PcieLinkUtil,
+    DiskRead,
+    DiskWrite,
+    DiskBusy,
+    NetRx,
+    NetTx,
+    PsiCpu,
+    PsiMemory,
+    PsiIo,
+    TempMax,
+    PowerPackage,
+    PowerCore,
+    PowerUncore,
+    PowerDram,
+    GbPerJoule,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 22] = [
+        Metric::CpuUsage,
+        Metric::RamUsed,
+        Metric::VramUsed,
+        Metric::VramPct,
+        Metric::GpuUtil,
+        Metric::PcieTx,
+        Metric::PcieRx,
+        Metric::PcieLinkUtil,
+        Metric::DiskRead,
+        Metric::DiskWrite,
+        Metric::DiskBusy,
+        Metric::NetRx,
+        Metric::NetTx,
+        Metric::PsiCpu,
+        Metric::PsiMemory,
+        Metric::PsiIo,
+        Metric::TempMax,
+        Metric::PowerPackage,
+        Metric::PowerCore,
+        Metric::PowerUncore,
+        Metric::PowerDram,
+        Metric::GbPerJoule,
+    ];
+
+    // Short machine-friendly name, used on the command line and in output formats.
+    pub fn key(self) -> &'static str {
+        match self {
+            Metric::CpuUsage => "cpu",
+            Metric::RamUsed => "ram",
+            Metric::VramUsed => "vram",
+            Metric::VramPct => "vram_pct",
+            Metric::GpuUtil => "gpu_util",
+            Metric::PcieTx => "pcie_tx",
+            Metric::PcieRx => "pcie_rx",
+            Metric::PcieLinkUtil => "pcie_link",
+            Metric::DiskRead => "disk_read",
+            Metric::DiskWrite => "disk_write",
+            Metric::DiskBusy => "disk_busy",
+            Metric::NetRx => "net_rx",
+            Metric::NetTx => "net_tx",
+            Metric::PsiCpu => "psi_cpu",
+            Metric::PsiMemory => "psi_memory",
+            Metric::PsiIo => "psi_io",
+            Metric::TempMax => "temp_max",
+            Metric::PowerPackage => "power_package",
+            Metric::PowerCore => "power_core",
+            Metric::PowerUncore => "power_uncore",
+            Metric::PowerDram => "power_dram",
+            Metric::GbPerJoule => "gb_per_joule",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Metric> {
+        Metric::ALL.into_iter().find(|m| m.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::CpuUsage => "CPU Usage",
+            Metric::RamUsed => "RAM Used",
+            Metric::VramUsed => "VRAM Used",
+            Metric::VramPct => "VRAM Used (fullest GPU)",
+            Metric::GpuUtil => "GPU Utilization",
+            Metric::PcieTx => "PCIe TX (Send)",
+            Metric::PcieRx => "PCIe RX (Receive)",
+            Metric::PcieLinkUtil => "PCIe Link (busiest GPU)",
+            Metric::DiskRead => "Disk Read",
+            Metric::DiskWrite => "Disk Write",
+            Metric::DiskBusy => "Disk Busy (busiest device)",
+            Metric::NetRx => "Network RX",
+            Metric::NetTx => "Network TX",
+            Metric::PsiCpu => "CPU Pressure",
+            Metric::PsiMemory => "Memory Pressure",
+            Metric::PsiIo => "I/O Pressure",
+            Metric::TempMax => "Hottest Sensor",
+            Metric::PowerPackage => "CPU Package",
+            Metric::PowerCore => "CPU Cores",
+            Metric::PowerUncore => "Uncore",
+            Metric::PowerDram => "DRAM",
+            Metric::GbPerJoule => "Data Moved per Joule",
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Metric::CpuUsage
+            | Metric::VramPct
+            | Metric::GpuUtil
+            | Metric::PcieLinkUtil
+            | Metric::DiskBusy
+            | Metric::PsiCpu
+            | Metric::PsiMemory
+            | Metric::PsiIo => "%",
+            Metric::RamUsed => "GB",
+            Metric::TempMax => "°C",
+            Metric::PowerPackage | Metric::PowerCore | Metric::PowerUncore | Metric::PowerDram => "W",
+            Metric::GbPerJoule => "GB/J",
+            Metric::VramUsed => "MB",
+            Metric::PcieTx | Metric::PcieRx | Metric::DiskRead | Metric::DiskWrite | Metric::NetRx | Metric::NetTx => {
+                "MB/s"
+            }
+        }
+    }
+
+    pub fn is_bandwidth(self) -> bool {
+        matches!(
+            self,
+            Metric::PcieTx | Metric::PcieRx | Metric::DiskRead | Metric::DiskWrite | Metric::NetRx | Metric::NetTx
+        )
+    }
+
+    // Id of the source that fills this metric in.
+    pub fn source_id(self) -> &'static str {
+        match self {
+            Metric::CpuUsage | Metric::RamUsed => "cpu",
+            Metric::VramUsed | Metric::VramPct | Metric::GpuUtil | Metric::PcieTx | Metric::PcieRx | Metric::PcieLinkUtil => "gpu",
+            Metric::DiskRead | Metric::DiskWrite | Metric::DiskBusy => "disk",
+            Metric::NetRx | Metric::NetTx => "net",
+            Metric::PsiCpu | Metric::PsiMemory | Metric::PsiIo => "psi",
+            Metric::TempMax => "sensors",
+            Metric::PowerPackage
+            | Metric::PowerCore
+            | Metric::PowerUncore
+            | Metric::PowerDram
+            | Metric::GbPerJoule => "power",
+        }
+    }
+
+    pub fn value(self, m: &HardwareMetrics) -> f64 {
+        let mib = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+        match self {
+            Metric::CpuUsage => m.cpu_usage as f64,
+            Metric::RamUsed => m.ram_used_gb as f64,
+            Metric::VramUsed => m.gpu_vram_used_mb as f64,
+            // Per card: one full GPU is an OOM waiting to happen, however empty the others are.
+            Metric::VramPct => m
+                .gpus
+                .iter()
+                .filter(|g| g.vram_total_mb > 0)
+                .map(|g| g.vram_used_mb as f64 / g.vram_total_mb as f64 * 100.0)
+                .fold(0.0, f64::max),
+            Metric::GpuUtil => m.gpu_util_pct as f64,
+            Metric::PcieTx => mib(m.gpu_pcie_tx),
+            Metric::PcieRx => mib(m.gpu_pcie_rx),
+            // 0 when no GPU reports its link; analysis falls back to the configured ceiling.
+            Metric::PcieLinkUtil => m.gpus.iter().filter_map(|g| g.pcie_util_pct()).fold(0.0, f32::max) as f64,
+            Metric::DiskRead => mib(m.disk_read_bps),
+            Metric::DiskWrite => mib(m.disk_write_bps),
+            Metric::NetRx => mib(m.net_rx_bps),
+            Metric::NetTx => mib(m.net_tx_bps),
+            Metric::DiskBusy => m.disks.iter().map(|d| d.busy_pct as f64).fold(0.0, f64::max),
+            // "some" over the last 10 s, the closest PSI has to an instantaneous reading.
+            Metric::PsiCpu => m.psi.cpu.map_or(0.0, |p| p.some.avg10 as f64),
+            Metric::PsiMemory => m.psi.memory.map_or(0.0, |p| p.some.avg10 as f64),
+            Metric::PsiIo => m.psi.io.map_or(0.0, |p| p.some.avg10 as f64),
+            // Across every chip: whichever part is about to throttle is the one that matters.
+            Metric::TempMax => m
+                .sensors
+                .iter()
+                .flat_map(|chip| &chip.sensors)
+                .filter(|s| s.kind == SensorKind::Temperature)
+                .map(|s| s.value)
+                .fold(0.0, f64::max),
+            Metric::PowerPackage => m.power_watts("package"),
+            Metric::PowerCore => m.power_watts("core"),
+            Metric::PowerUncore => m.power_watts("uncore"),
+            Metric::PowerDram => m.power_watts("dram"),
+            Metric::GbPerJoule => m.bytes_per_joule().map_or(0.0, |b| b / 1024.0 / 1024.0 / 1024.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn cgroup(path: &str) -> CgroupMetrics {
+        CgroupMetrics { path: path.to_string(), ..Default::default() }
+    }
+
+    fn process(pid: u32, cgroup: &str) -> ProcessMetrics {
+        ProcessMetrics { pid, cgroup: cgroup.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn cgroup_parent_and_contains() {
+        let slice = cgroup("/system.slice");
+        let service = cgroup("/system.slice/docker-1f2e.scope");
+        assert_eq!(slice.parent(), None);
+        assert_eq!(service.parent(), Some("/system.slice"));
+        assert_eq!(service.name(), "docker-1f2e.scope");
+
+        assert!(slice.contains("/system.slice"));
+        assert!(slice.contains("/system.slice/docker-1f2e.scope/init"));
+        // A sibling sharing the prefix is not inside.
+        assert!(!slice.contains("/system.slice2"));
+        assert!(!service.contains("/system.slice"));
+    }
+
+    #[test]
+    fn scoped_to_a_cgroup() {
+        let container = CgroupMetrics {
+            cpu_pct: 400.0,
+            memory_bytes: 2 * GIB,
+            memory_max_bytes: Some(4 * GIB),
+            read_bps: 10_000,
+            write_bps: 2_000,
+            io: vec![CgroupIoMetrics { device: "nvme0n1".to_string(), read_bps: 10_000, write_bps: 2_000 }],
+            ..cgroup("/system.slice/docker-1f2e.scope")
+        };
+        let host = HardwareMetrics {
+            cpu_usage: 90.0,
+            cores: (0..8).map(|cpu| CoreMetrics { cpu, ..Default::default() }).collect(),
+            ram_total_gb: 64.0,
+            ram_used_gb: 40.0,
+            disk_read_bps: 900_000,
+            disks: vec![
+                DiskDeviceMetrics { name: "nvme0n1".to_string(), read_bps: 800_000, read_iops: 500.0, ..Default::default() },
+                DiskDeviceMetrics { name: "sda".to_string(), read_bps: 100_000, ..Default::default() },
+            ],
+            gpu_pcie_rx: 123,
+            processes: vec![
+                process(1, "/system.slice/docker-1f2e.scope"),
+                process(2, "/system.slice/docker-1f2e.scope/app"),
+                process(3, "/system.slice/docker-1f2e.scope2"),
+                process(4, "/user.slice"),
+            ],
+            cgroups: vec![cgroup("/system.slice"), container],
+            ..Default::default()
+        };
+
+        let m = host.scoped_to("/system.slice/docker-1f2e.scope").unwrap();
+        // Four of the eight CPUs.
+        assert_eq!(m.cpu_usage, 50.0);
+        assert_eq!((m.ram_used_gb, m.ram_total_gb), (2.0, 4.0));
+        assert_eq!((m.disk_read_bps, m.disk_write_bps), (10_000, 2_000));
+        assert_eq!(m.disks.len(), 1);
+        assert_eq!((m.disks[0].read_bps, m.disks[0].read_iops), (10_000, 500.0));
+        assert_eq!(m.processes.iter().map(|p| p.pid).collect::<Vec<_>>(), [1, 2]);
+        // Not accounted per cgroup.
+        assert_eq!(m.gpu_pcie_rx, 123);
+
+        // Unlimited memory leaves the host's total.
+        assert_eq!(host.scoped_to("/system.slice").unwrap().ram_total_gb, 64.0);
+        assert!(host.scoped_to("/gone.scope").is_none());
+    }
+}
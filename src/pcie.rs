@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use crate::metrics::PcieLink;
+
+// Where Linux exposes PCI devices, one directory per bus address.
+pub const SYS_PCI_DEVICES: &str = "/sys/bus/pci/devices";
+
+// Usable bytes/s of one lane in one direction for a PCIe generation:
+// transfer rate times line-code efficiency (8b/10b up to Gen2, 128b/130b
+// for Gen3-5, FLIT mode with 242/256 for Gen6).
+pub fn lane_bytes_per_sec(gen: u32) -> Option<f64> {
+    let (gigatransfers, efficiency) = match gen {
+        1 => (2.5, 8.0 / 10.0),
+        2 => (5.0, 8.0 / 10.0),
+        3 => (8.0, 128.0 / 130.0),
+        4 => (16.0, 128.0 / 130.0),
+        5 => (32.0, 128.0 / 130.0),
+        6 => (64.0, 242.0 / 256.0),
+        _ => return None,
+    };
+    Some(gigatransfers * 1e9 * efficiency / 8.0)
+}
+
+// Theoretical per-direction bandwidth of a link, before protocol overhead
+// (TLP headers, flow control), which costs another 10-20% in practice.
+pub fn link_bytes_per_sec(gen: u32, width: u32) -> Option<f64> {
+    lane_bytes_per_sec(gen).map(|lane| lane * width as f64)
+}
+
+// Maps the sysfs link speed string ("16.0 GT/s PCIe", "8 GT/s") to a generation.
+pub fn parse_link_speed(text: &str) -> Option<u32> {
+    let rate: f64 = text.split_whitespace().next()?.parse().ok()?;
+    [(2.5, 1), (5.0, 2), (8.0, 3), (16.0, 4), (32.0, 5), (64.0, 6)]
+        .into_iter()
+        .find(|&(gt, _)| (rate - gt).abs() < 0.1)
+        .map(|(_, gen)| gen)
+}
+
+// NVML reports bus ids with a 32-bit domain ("00000000:17:00.0"), sysfs and
+// lspci with a 16-bit one ("0000:17:00.0"). Lower-cases the hex as well.
+pub fn sysfs_address(bus_id: &str) -> String {
+    let bus_id = bus_id.to_ascii_lowercase();
+    match bus_id.split_once(':') {
+        Some((domain, rest)) if domain.len() > 4 => format!("{}:{}", &domain[domain.len() - 4..], rest),
+        _ => bus_id,
+    }
+}
+
+// Reads the negotiated and maximum link of a device from sysfs. None if the
+// device or any of the four attributes is missing (e.g. integrated devices).
+pub fn sysfs_link(sys_pci_devices: &Path, bus_id: &str) -> Option<PcieLink> {
+    let dir = sys_pci_devices.join(sysfs_address(bus_id));
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+    Some(PcieLink {
+        gen: parse_link_speed(&read("current_link_speed")?)?,
+        width: read("current_link_width")?.trim().parse().ok()?,
+        max_gen: parse_link_speed(&read("max_link_speed")?)?,
+        max_width: read("max_link_width")?.trim().parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e3
+    }
+
+    #[test]
+    fn lane_bandwidth_per_generation() {
+        let table = [
+            (1, 250e6),
+            (2, 500e6),
+            (3, 984_615_384.6),
+            (4, 1_969_230_769.2),
+            (5, 3_938_461_538.5),
+            (6, 7_562_500_000.0),
+        ];
+        for (gen, bytes) in table {
+            let lane = lane_bytes_per_sec(gen).unwrap();
+            assert!(close(lane, bytes), "Gen{}: {}", gen, lane);
+        }
+        assert_eq!(lane_bytes_per_sec(0), None);
+        assert_eq!(lane_bytes_per_sec(7), None);
+    }
+
+    #[test]
+    fn link_bandwidth_scales_with_width() {
+        let table = [
+            (3, 16, 15_753_846_153.8),
+            (4, 16, 31_507_692_307.7),
+            (4, 8, 15_753_846_153.8),
+            (4, 1, 1_969_230_769.2),
+            (5, 4, 15_753_846_153.8),
+        ];
+        for (gen, width, bytes) in table {
+            let link = link_bytes_per_sec(gen, width).unwrap();
+            assert!(close(link, bytes), "Gen{} x{}: {}", gen, width, link);
+        }
+        assert_eq!(link_bytes_per_sec(9, 16), None);
+
+        // A x16 Gen4 card that trained at x8: half the ceiling, flagged narrow.
+        let degraded = PcieLink { gen: 4, width: 8, max_gen: 4, max_width: 16 };
+        assert!(degraded.narrower_than_max() && !degraded.slower_than_max());
+        assert!(close(degraded.bandwidth_bps().unwrap() * 2.0, link_bytes_per_sec(4, 16).unwrap()));
+        // Idle cards drop a generation to save power; that's not narrow.
+        let idle = PcieLink { gen: 1, width: 16, max_gen: 4, max_width: 16 };
+        assert!(idle.slower_than_max() && !idle.narrower_than_max());
+    }
+
+    #[test]
+    fn parses_link_speeds() {
+        let table = [
+            ("2.5 GT/s PCIe", Some(1)),
+            ("5.0 GT/s PCIe", Some(2)),
+            ("8 GT/s", Some(3)),
+            ("16.0 GT/s PCIe\n", Some(4)),
+            ("32.0 GT/s PCIe", Some(5)),
+            ("64.0 GT/s PCIe", Some(6)),
+        ];
+        for (text, gen) in table {
+            assert_eq!(parse_link_speed(text), gen, "{:?}", text);
+        }
+        assert_eq!(parse_link_speed("Unknown"), None);
+        assert_eq!(parse_link_speed("12.0 GT/s"), None);
+        assert_eq!(parse_link_speed(""), None);
+    }
+
+    #[test]
+    fn converts_bus_ids_to_sysfs_addresses() {
+        assert_eq!(sysfs_address("00000000:17:00.0"), "0000:17:00.0");
+        assert_eq!(sysfs_address("00000001:AF:00.1"), "0001:af:00.1");
+        assert_eq!(sysfs_address("0000:3B:00.0"), "0000:3b:00.0");
+        // Malformed ids pass through lower-cased; the sysfs lookup then misses.
+        assert_eq!(sysfs_address("GPU-1234"), "gpu-1234");
+        assert_eq!(sysfs_address(""), "");
+        assert!(sysfs_link(Path::new("/nonexistent"), "not an address").is_none());
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+use crate::metrics::HardwareMetrics;
+
+// File layout: MAGIC, then one record per sample, each a little-endian u32
+// length followed by that many bytes of bincode-encoded HardwareMetrics.
+// The length prefix lets a reader stop cleanly at a record that was only
+// half written when the recorder was killed.
+//
+// bincode has no notion of optional fields, so the version digits are bumped
+// whenever the layout of HardwareMetrics changes.
+const MAGIC: &[u8; 8] = b"HWMREC15";
+
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(MAGIC).map_err(|e| e.to_string())?;
+        Ok(Self { writer })
+    }
+
+    // Appends one sample and flushes, so a crash loses at most the sample in flight.
+    pub fn record(&mut self, m: &HardwareMetrics) -> Result<(), String> {
+        let bytes = bincode::serialize(m).map_err(|e| e.to_string())?;
+        self.writer.write_all(&(bytes.len() as u32).to_le_bytes()).map_err(|e| e.to_string())?;
+        self.writer.write_all(&bytes).map_err(|e| e.to_string())?;
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+// Loads every complete sample of a recording, oldest first.
+pub fn load(path: &Path) -> Result<Vec<HardwareMetrics>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 8];
+    if reader.read_exact(&mut magic).is_err() || &magic != MAGIC {
+        return Err(format!("{}: not a hw-mon recording", path.display()));
+    }
+
+    let mut samples = Vec::new();
+    loop {
+        let mut len = [0u8; 4];
+        match reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.to_string()),
+        }
+        let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+        if reader.read_exact(&mut bytes).is_err() {
+            // Truncated final record.
+            break;
+        }
+        match bincode::deserialize(&bytes) {
+            Ok(m) => samples.push(m),
+            Err(e) => return Err(format!("{}: corrupt record {}: {}", path.display(), samples.len(), e)),
+        }
+    }
+    Ok(samples)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::metrics::{CgroupMetrics, ProcessMetrics};
+    use crate::test_util::TempTree;
+
+    #[test]
+    fn processes_and_cgroups_are_live_only() {
+        let tree = TempTree::new();
+        let path = tree.path("run.hwm");
+        let m = HardwareMetrics {
+            cpu_usage: 42.0,
+            processes: vec![ProcessMetrics { pid: 1, name: "init".to_string(), ..Default::default() }],
+            cgroups: vec![CgroupMetrics { path: "/init.scope".to_string(), ..Default::default() }],
+            ..Default::default()
+        };
+        let mut recorder = Recorder::create(&path).unwrap();
+        recorder.record(&m).unwrap();
+        recorder.record(&m).unwrap();
+
+        let samples = load(&path).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].cpu_usage, 42.0);
+        assert!(samples[1].processes.is_empty() && samples[1].cgroups.is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+use crate::history::History;
+use crate::metrics::HardwareMetrics;
+
+pub const SPEEDS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 5.0, 10.0];
+
+// Plays back a recording on a virtual clock. `position` is seconds since the
+// first sample; the current sample is the last one taken at or before it.
+pub struct Player {
+    samples: Vec<HardwareMetrics>,
+    start: f64,
+    duration: f64,
+    position: f64,
+    pub playing: bool,
+    pub speed: f32,
+    last_tick: Instant,
+}
+
+impl Player {
+    pub fn new(samples: Vec<HardwareMetrics>) -> Result<Self, String> {
+        let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+            return Err("recording contains no samples".to_string());
+        };
+        let start = first.timestamp_secs();
+        let duration = last.timestamp_secs() - start;
+        Ok(Self { samples, start, duration, position: 0.0, playing: true, speed: 1.0, last_tick: Instant::now() })
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn seek(&mut self, position: f64) {
+        self.position = position.clamp(0.0, self.duration);
+    }
+
+    // Advances the virtual clock by the wall time since the last tick. Stops at the end.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_tick).as_secs_f64();
+        self.last_tick = now;
+        if self.playing {
+            self.seek(self.position + elapsed * self.speed as f64);
+            if self.position >= self.duration {
+                self.playing = false;
+            }
+        }
+    }
+
+    fn index_at(&self, position: f64) -> usize {
+        let t = self.start + position;
+        self.samples.partition_point(|m| m.timestamp_secs() <= t).saturating_sub(1)
+    }
+
+    pub fn current(&self) -> &HardwareMetrics {
+        &self.samples[self.index_at(self.position)]
+    }
+
+    // Indices of the samples a live history would hold at the current
+    // position. The history only changes when this does.
+    pub fn history_range(&self, window: Duration) -> RangeInclusive<usize> {
+        let end = self.index_at(self.position);
+        let oldest = self.start + self.position - window.as_secs_f64();
+        let begin = self.samples.partition_point(|m| m.timestamp_secs() < oldest);
+        begin.min(end)..=end
+    }
+
+    // History as it would have looked live at the current position.
+    pub fn history(&self, window: Duration) -> History {
+        let mut history = History::new(window);
+        for m in &self.samples[self.history_range(window)] {
+            history.push(m);
+        }
+        history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn player(seconds: u64) -> Player {
+        let samples = (0..seconds)
+            .map(|second| HardwareMetrics {
+                timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + second),
+                ..Default::default()
+            })
+            .collect();
+        Player::new(samples).unwrap()
+    }
+
+    #[test]
+    fn history_range_follows_the_position() {
+        let mut player = player(10);
+        let window = Duration::from_secs(3);
+        assert_eq!(player.history_range(window), 0..=0);
+        player.seek(5.5);
+        assert_eq!(player.history_range(window), 3..=5);
+        // Within the same sample, nothing changes.
+        player.seek(5.9);
+        assert_eq!(player.history_range(window), 3..=5);
+        assert_eq!(player.history_range(Duration::from_secs(60)), 0..=5);
+        player.seek(100.0);
+        assert_eq!(player.history_range(window), 6..=9);
+        assert_eq!(player.history(window).series(crate::metrics::Metric::CpuUsage).count(), 4);
+    }
+}
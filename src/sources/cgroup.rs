@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::psi::read_psi;
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{CgroupIoMetrics, CgroupMetrics, HardwareMetrics, Psi};
+
+// usage_usec from cpu.stat: CPU time of every task in the cgroup and its
+// descendants, in microseconds.
+pub fn parse_cpu_stat(text: &str) -> Option<u64> {
+    text.lines().find_map(|line| line.strip_prefix("usage_usec ")?.trim().parse().ok())
+}
+
+// memory.max holds a byte count or "max".
+pub fn parse_memory_max(text: &str) -> Option<u64> {
+    text.trim().parse().ok()
+}
+
+// One line per device from io.stat:
+//
+//   259:0 rbytes=1617920 wbytes=0 rios=150 wios=0 dbytes=0 dios=0
+//
+// Returns (major:minor, rbytes, wbytes).
+pub fn parse_io_stat(text: &str) -> Vec<(String, u64, u64)> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?.to_string();
+            let (mut read, mut write) = (0, 0);
+            for field in fields {
+                match field.split_once('=') {
+                    Some(("rbytes", v)) => read = v.parse().ok()?,
+                    Some(("wbytes", v)) => write = v.parse().ok()?,
+                    _ => {}
+                }
+            }
+            Some((device, read, write))
+        })
+        .collect()
+}
+
+// Block device name for a "major:minor" from /sys/dev/block, whose entries
+// link to the device's sysfs directory.
+pub fn block_device_name(sys_dev_block: &Path, device: &str) -> Option<String> {
+    let target = std::fs::read_link(sys_dev_block.join(device)).ok()?;
+    target.file_name()?.to_str().map(str::to_string)
+}
+
+struct CgroupSample {
+    usage_usec: u64,
+    memory_bytes: u64,
+    memory_max_bytes: Option<u64>,
+    // major:minor -> (rbytes, wbytes)
+    io: HashMap<String, (u64, u64)>,
+    psi: Psi,
+}
+
+// Per-cgroup CPU, memory and block I/O from the cgroup v2 hierarchy. Covers
+// containers and systemd services alike, since both are just cgroups.
+pub struct CgroupSource {
+    root: PathBuf,
+    sys_dev_block: PathBuf,
+    device_names: HashMap<String, String>,
+    previous: HashMap<String, CgroupSample>,
+    previous_at: Option<Instant>,
+    health: SourceHealth,
+}
+
+impl CgroupSource {
+    pub fn new() -> Self {
+        Self::with_paths("/sys/fs/cgroup", "/sys/dev/block")
+    }
+
+    // Lets the source run against a fake cgroupfs/sysfs tree.
+    pub fn with_paths(root: impl Into<PathBuf>, sys_dev_block: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            sys_dev_block: sys_dev_block.into(),
+            device_names: HashMap::new(),
+            previous: HashMap::new(),
+            previous_at: None,
+            health: SourceHealth::Pending,
+        }
+    }
+
+    fn read_cgroup(dir: &Path) -> CgroupSample {
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+        // Controllers that aren't enabled for a subtree simply have no files.
+        CgroupSample {
+            usage_usec: read("cpu.stat").and_then(|text| parse_cpu_stat(&text)).unwrap_or(0),
+            memory_bytes: read("memory.current").and_then(|text| text.trim().parse().ok()).unwrap_or(0),
+            memory_max_bytes: read("memory.max").and_then(|text| parse_memory_max(&text)),
+            io: read("io.stat")
+                .map(|text| parse_io_stat(&text).into_iter().map(|(dev, r, w)| (dev, (r, w))).collect())
+                .unwrap_or_default(),
+            psi: read_psi(dir, ".pressure"),
+        }
+    }
+
+    // Every cgroup below `dir`, depth first, keyed by its path relative to the root.
+    fn walk(&self, dir: &Path, path: &str, out: &mut Vec<(String, CgroupSample)>) {
+        let Ok(entries) = std::fs::read_dir(dir) else { return };
+        let mut children: Vec<(String, PathBuf)> = entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter_map(|e| Some((e.file_name().to_str()?.to_string(), e.path())))
+            .collect();
+        children.sort();
+        for (name, child) in children {
+            let child_path = format!("{}/{}", path, name);
+            out.push((child_path.clone(), Self::read_cgroup(&child)));
+            self.walk(&child, &child_path, out);
+        }
+    }
+
+    fn read(&self) -> Result<Vec<(String, CgroupSample)>, String> {
+        // Only the v2 root has cgroup.controllers; v1 and hybrid hosts mount
+        // one directory per controller there instead.
+        if !self.root.join("cgroup.controllers").exists() {
+            return Err(format!("{} is not a cgroup v2 hierarchy", self.root.display()));
+        }
+        let mut out = Vec::new();
+        self.walk(&self.root, "", &mut out);
+        Ok(out)
+    }
+}
+
+// block_device_name with a cache; device numbers don't change while the device exists.
+fn device_name(cache: &mut HashMap<String, String>, sys_dev_block: &Path, device: &str) -> String {
+    cache
+        .entry(device.to_string())
+        .or_insert_with(|| block_device_name(sys_dev_block, device).unwrap_or_else(|| device.to_string()))
+        .clone()
+}
+
+impl MetricSource for CgroupSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "cgroup", description: "cgroups (/sys/fs/cgroup)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        match self.read() {
+            Ok(samples) => {
+                self.previous = samples.into_iter().collect();
+                self.previous_at = Some(Instant::now());
+                self.health = SourceHealth::Ok;
+                Ok(())
+            }
+            Err(e) => {
+                self.health = SourceHealth::Unavailable(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let samples = match self.read() {
+            Ok(samples) => samples,
+            Err(e) => {
+                self.health = SourceHealth::Degraded(e);
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        let elapsed = self.previous_at.map(|t| now.duration_since(t).as_secs_f64()).unwrap_or(0.0);
+        if elapsed > 0.0 {
+            // Counters restart from zero when a cgroup is recreated under the same name.
+            let rate = |now: u64, before: u64| (now.saturating_sub(before) as f64 / elapsed) as u64;
+            let mut cgroups = Vec::with_capacity(samples.len());
+            for (path, cur) in &samples {
+                // New cgroups show up once they have a previous sample to diff against.
+                let Some(prev) = self.previous.get(path) else { continue };
+                let mut io: Vec<CgroupIoMetrics> = cur
+                    .io
+                    .iter()
+                    .map(|(device, &(read, write))| {
+                        let (read_before, write_before) = prev.io.get(device).copied().unwrap_or((read, write));
+                        CgroupIoMetrics {
+                            device: device_name(&mut self.device_names, &self.sys_dev_block, device),
+                            read_bps: rate(read, read_before),
+                            write_bps: rate(write, write_before),
+                        }
+                    })
+                    .collect();
+                io.sort_by(|a, b| a.device.cmp(&b.device));
+                cgroups.push(CgroupMetrics {
+                    path: path.clone(),
+                    cpu_pct: (cur.usage_usec.saturating_sub(prev.usage_usec) as f64 / 1e6 / elapsed * 100.0) as f32,
+                    memory_bytes: cur.memory_bytes,
+                    memory_max_bytes: cur.memory_max_bytes,
+                    read_bps: io.iter().map(|d| d.read_bps).sum(),
+                    write_bps: io.iter().map(|d| d.write_bps).sum(),
+                    io,
+                    psi: cur.psi.clone(),
+                });
+            }
+            m.cgroups = cgroups;
+        }
+        self.previous = samples.into_iter().collect();
+        self.previous_at = Some(now);
+        self.health = SourceHealth::Ok;
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    #[test]
+    fn parses_io_stat() {
+        let text = "\
+259:0 rbytes=1617920 wbytes=4096 rios=150 wios=1 dbytes=0 dios=0
+8:16 rbytes=0 wbytes=0 rios=0 wios=0 dbytes=0 dios=0
+253:1 rbytes=oops wbytes=1
+7:0
+";
+        let io = parse_io_stat(text);
+        assert_eq!(io, [("259:0".to_string(), 1617920, 4096), ("8:16".to_string(), 0, 0), ("7:0".to_string(), 0, 0)]);
+        assert!(parse_io_stat("").is_empty());
+    }
+
+    #[test]
+    fn parses_cpu_and_memory_files() {
+        assert_eq!(parse_cpu_stat("usage_usec 8123456\nuser_usec 6000000\nsystem_usec 2123456\n"), Some(8123456));
+        assert_eq!(parse_memory_max("max\n"), None);
+        assert_eq!(parse_memory_max("4294967296\n"), Some(4294967296));
+    }
+
+    #[test]
+    fn names_devices_through_sys_dev_block() {
+        let tree = TempTree::new();
+        tree.symlink("dev/block/259:0", "../../devices/pci0000:00/0000:00:1d.0/0000:3d:00.0/nvme/nvme0/nvme0n1");
+        assert_eq!(block_device_name(&tree.path("dev/block"), "259:0").as_deref(), Some("nvme0n1"));
+        assert_eq!(block_device_name(&tree.path("dev/block"), "8:0"), None);
+    }
+
+    #[test]
+    fn source_walks_the_hierarchy() {
+        let tree = TempTree::new();
+        tree.write("cgroup/cgroup.controllers", "cpuset cpu io memory pids\n");
+        let cgroup = |path: &str, usage_usec: u64, rbytes: u64| {
+            tree.write(&format!("cgroup{}/cpu.stat", path), &format!("usage_usec {}\nuser_usec 0\n", usage_usec));
+            tree.write(&format!("cgroup{}/memory.current", path), "1048576\n");
+            tree.write(&format!("cgroup{}/memory.max", path), "max\n");
+            tree.write(&format!("cgroup{}/io.stat", path), &format!("259:0 rbytes={} wbytes=0 rios=1 wios=0\n", rbytes));
+        };
+        cgroup("/system.slice", 0, 0);
+        cgroup("/system.slice/docker-1f2e.scope", 0, 0);
+        tree.write("cgroup/system.slice/docker-1f2e.scope/memory.max", "2147483648\n");
+        tree.symlink("dev/block/259:0", "../../devices/nvme/nvme0/nvme0n1");
+
+        let mut source = CgroupSource::with_paths(tree.path("cgroup"), tree.path("dev/block"));
+        source.init().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cgroup("/system.slice", 10_000, 4096);
+        cgroup("/user.slice", 10_000, 0);
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+
+        // user.slice is new and has no baseline yet.
+        let paths: Vec<&str> = m.cgroups.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["/system.slice", "/system.slice/docker-1f2e.scope"]);
+        let slice = &m.cgroups[0];
+        assert!(slice.cpu_pct > 0.0 && slice.read_bps > 0);
+        assert_eq!((slice.memory_bytes, slice.memory_max_bytes), (1048576, None));
+        assert_eq!(slice.io[0].device, "nvme0n1");
+        assert_eq!(m.cgroups[1].memory_max_bytes, Some(2147483648));
+
+        let mut v1 = CgroupSource::with_paths(tree.path("dev"), tree.path("dev/block"));
+        assert!(v1.init().is_err());
+    }
+}
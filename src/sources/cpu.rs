@@ -0,0 +1,55 @@
+use sysinfo::System;
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{CoreMetrics, HardwareMetrics};
+
+// CPU & RAM usage via sysinfo, for platforms without a procfs source.
+pub struct CpuRamSource {
+    sys: Option<System>,
+}
+
+impl CpuRamSource {
+    pub fn new() -> Self {
+        Self { sys: None }
+    }
+}
+
+impl MetricSource for CpuRamSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "cpu", description: "CPU & RAM (sysinfo)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        self.sys = Some(System::new());
+        Ok(())
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let Some(sys) = self.sys.as_mut() else { return };
+        // Only what we read; refresh_all() would also walk every process.
+        sys.refresh_cpu_all();
+        sys.refresh_memory();
+
+        m.cpu_usage = sys.global_cpu_usage();
+        // sysinfo has no SMT topology, so every logical CPU is its own core.
+        m.cores = sys
+            .cpus()
+            .iter()
+            .enumerate()
+            .map(|(i, cpu)| CoreMetrics {
+                cpu: i as u32,
+                core_id: i as u32,
+                package_id: 0,
+                usage: cpu.cpu_usage(),
+                freq_mhz: cpu.frequency() as u32,
+                max_freq_mhz: 0,
+            })
+            .collect();
+        m.ram_used_gb = sys.used_memory() as f32 / 1024.0 / 1024.0 / 1024.0;
+        m.ram_total_gb = sys.total_memory() as f32 / 1024.0 / 1024.0 / 1024.0;
+    }
+
+    fn health(&self) -> SourceHealth {
+        if self.sys.is_some() { SourceHealth::Ok } else { SourceHealth::Pending }
+    }
+}
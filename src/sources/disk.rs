@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use windows_sys::Win32::System::Performance::{
+    PdhAddEnglishCounterW, PdhCloseQuery, PdhCollectQueryData, PdhGetFormattedCounterArrayW, PdhOpenQueryW,
+    PDH_CSTATUS_NEW_DATA, PDH_CSTATUS_VALID_DATA, PDH_FMT_COUNTERVALUE_ITEM_W, PDH_FMT_DOUBLE, PDH_MORE_DATA,
+};
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{DiskDeviceMetrics, HardwareMetrics};
+
+// Not exported by windows-sys 0.52. Without it "% Idle Time" is clamped to 100,
+// which it exceeds slightly on busy multi-queue devices.
+const PDH_FMT_NOCAP100: u32 = 0x8000;
+
+// One wildcard counter per column of the per-device table. The `_Total`
+// instance of the byte counters feeds the global totals.
+const COUNTERS: [&str; 7] = [
+    r"\PhysicalDisk(*)\Disk Read Bytes/sec",
+    r"\PhysicalDisk(*)\Disk Write Bytes/sec",
+    r"\PhysicalDisk(*)\Disk Reads/sec",
+    r"\PhysicalDisk(*)\Disk Writes/sec",
+    r"\PhysicalDisk(*)\Avg. Disk sec/Transfer",
+    r"\PhysicalDisk(*)\Avg. Disk Queue Length",
+    r"\PhysicalDisk(*)\% Idle Time",
+];
+
+const TOTAL_INSTANCE: &str = "_Total";
+
+// Disk bandwidth and per-device I/O statistics via the Windows Performance Data Helper (PDH).
+pub struct PdhDiskSource {
+    query: isize,
+    counters: [isize; COUNTERS.len()],
+    // PDH only reports rates, so running totals are integrated here:
+    // [read bytes, write bytes, reads, writes] per instance.
+    totals: HashMap<String, [f64; 4]>,
+    last_sample: Option<Instant>,
+    health: SourceHealth,
+}
+
+impl PdhDiskSource {
+    pub fn new() -> Self {
+        Self {
+            query: 0,
+            counters: [0; COUNTERS.len()],
+            totals: HashMap::new(),
+            last_sample: None,
+            health: SourceHealth::Pending,
+        }
+    }
+
+    // Reads every instance of a wildcard counter from the most recent
+    // PdhCollectQueryData call as (instance name, value) pairs.
+    fn read_counter_array(counter: isize) -> Vec<(String, f64)> {
+        let format = PDH_FMT_DOUBLE | PDH_FMT_NOCAP100;
+        let mut size: u32 = 0;
+        let mut count: u32 = 0;
+        unsafe {
+            // The first call only reports how large the buffer has to be.
+            let status = PdhGetFormattedCounterArrayW(counter, format, &mut size, &mut count, std::ptr::null_mut());
+            if status != PDH_MORE_DATA {
+                return Vec::new();
+            }
+
+            // The buffer holds `count` items followed by the instance name strings
+            // they point into. A Vec<u64> keeps it aligned for the item structs.
+            let mut buffer: Vec<u64> = vec![0; (size as usize).div_ceil(8)];
+            let items = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
+            if PdhGetFormattedCounterArrayW(counter, format, &mut size, &mut count, items) != 0 {
+                return Vec::new();
+            }
+
+            std::slice::from_raw_parts(items, count as usize)
+                .iter()
+                .filter(|item| matches!(item.FmtValue.CStatus, PDH_CSTATUS_VALID_DATA | PDH_CSTATUS_NEW_DATA))
+                .map(|item| (wide_to_string(item.szName), item.FmtValue.Anonymous.doubleValue))
+                .collect()
+        }
+    }
+}
+
+// Copies a NUL-terminated UTF-16 string owned by PDH.
+unsafe fn wide_to_string(ptr: *const u16) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    let mut len = 0;
+    while *ptr.add(len) != 0 {
+        len += 1;
+    }
+    String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len))
+}
+
+impl MetricSource for PdhDiskSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "disk", description: "Disk bandwidth (Windows PDH)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        unsafe {
+            let status = PdhOpenQueryW(std::ptr::null(), 0, &mut self.query);
+            if status != 0 {
+                self.query = 0;
+                let err = format!("PdhOpenQueryW failed (0x{:08x})", status);
+                self.health = SourceHealth::Unavailable(err.clone());
+                return Err(err);
+            }
+            // English counter names work regardless of the system display language.
+            for (path, counter) in COUNTERS.iter().zip(self.counters.iter_mut()) {
+                let path: Vec<u16> = path.encode_utf16().chain(Some(0)).collect();
+                PdhAddEnglishCounterW(self.query, path.as_ptr(), 0, counter);
+            }
+            // Rate counters need two collections before they produce a value.
+            PdhCollectQueryData(self.query);
+        }
+        self.health = SourceHealth::Ok;
+        Ok(())
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let status = unsafe { PdhCollectQueryData(self.query) };
+        if status != 0 {
+            self.health = SourceHealth::Degraded(format!("PdhCollectQueryData failed (0x{:08x})", status));
+            return;
+        }
+
+        let mut disks: Vec<DiskDeviceMetrics> = Vec::new();
+        for (column, &counter) in self.counters.iter().enumerate() {
+            for (instance, value) in Self::read_counter_array(counter) {
+                if instance == TOTAL_INSTANCE {
+                    match column {
+                        0 => m.disk_read_bps = value as u64,
+                        1 => m.disk_write_bps = value as u64,
+                        _ => {}
+                    }
+                    continue;
+                }
+                let index = match disks.iter().position(|d| d.name == instance) {
+                    Some(index) => index,
+                    None => {
+                        disks.push(DiskDeviceMetrics { name: instance, ..Default::default() });
+                        disks.len() - 1
+                    }
+                };
+                let disk = &mut disks[index];
+                match column {
+                    0 => disk.read_bps = value as u64,
+                    1 => disk.write_bps = value as u64,
+                    2 => disk.read_iops = value as f32,
+                    3 => disk.write_iops = value as f32,
+                    4 => disk.avg_latency_ms = (value * 1000.0) as f32,
+                    5 => disk.queue_depth = value as f32,
+                    _ => disk.busy_pct = (100.0 - value).clamp(0.0, 100.0) as f32,
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let elapsed = self.last_sample.map(|t| now.duration_since(t).as_secs_f64()).unwrap_or(0.0);
+        self.last_sample = Some(now);
+        for disk in disks.iter_mut() {
+            let totals = self.totals.entry(disk.name.clone()).or_insert([0.0; 4]);
+            let rates = [disk.read_bps as f64, disk.write_bps as f64, disk.read_iops as f64, disk.write_iops as f64];
+            for (total, rate) in totals.iter_mut().zip(rates) {
+                *total += rate * elapsed;
+            }
+            disk.read_bytes_total = totals[0] as u64;
+            disk.write_bytes_total = totals[1] as u64;
+            disk.reads_total = totals[2] as u64;
+            disk.writes_total = totals[3] as u64;
+        }
+
+        disks.sort_by(|a, b| a.name.cmp(&b.name));
+        m.disks = disks;
+        self.health = SourceHealth::Ok;
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+impl Drop for PdhDiskSource {
+    fn drop(&mut self) {
+        if self.query != 0 {
+            unsafe {
+                PdhCloseQuery(self.query);
+            }
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{DiskDeviceMetrics, HardwareMetrics};
+
+// /proc/diskstats always counts in 512-byte sectors, whatever the device's
+// logical block size is.
+const SECTOR_BYTES: u64 = 512;
+
+// One line of /proc/diskstats. Only the fields we use are kept; see
+// Documentation/admin-guide/iostats.rst for the full layout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiskStat {
+    pub name: String,
+    pub reads: u64,
+    pub sectors_read: u64,
+    pub read_ms: u64,
+    pub writes: u64,
+    pub sectors_written: u64,
+    pub write_ms: u64,
+    pub io_ms: u64,
+    pub weighted_io_ms: u64,
+}
+
+// Parses the contents of /proc/diskstats. Malformed lines are skipped.
+pub fn parse_diskstats(text: &str) -> Vec<DiskStat> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 14 {
+                return None;
+            }
+            let num = |i: usize| fields[i].parse::<u64>().ok();
+            Some(DiskStat {
+                name: fields[2].to_string(),
+                reads: num(3)?,
+                sectors_read: num(5)?,
+                read_ms: num(6)?,
+                writes: num(7)?,
+                sectors_written: num(9)?,
+                write_ms: num(10)?,
+                io_ms: num(12)?,
+                weighted_io_ms: num(13)?,
+            })
+        })
+        .collect()
+}
+
+// A device counts towards the total only if it is a whole disk (it has its
+// own entry under /sys/block, partitions don't) backed by real hardware
+// (it has a `device` link, which loop, ram, zram, dm-* and md* lack).
+pub fn is_physical_disk(sys_block: &Path, name: &str) -> bool {
+    // Names containing '/' (e.g. cciss/c0d0) are spelled with '!' in sysfs.
+    let dir = sys_block.join(name.replace('/', "!"));
+    dir.is_dir() && dir.join("device").exists()
+}
+
+// Turns two readings of the same device taken `elapsed` seconds apart into
+// rates, the same way iostat computes r/s, w/s, await, aqu-sz and %util.
+pub fn device_rates(prev: &DiskStat, cur: &DiskStat, elapsed: f64) -> DiskDeviceMetrics {
+    // Counters only go backwards when a device is removed and re-added.
+    let delta = |now: u64, before: u64| now.saturating_sub(before) as f64;
+    let reads = delta(cur.reads, prev.reads);
+    let writes = delta(cur.writes, prev.writes);
+    let io_wait_ms = delta(cur.read_ms, prev.read_ms) + delta(cur.write_ms, prev.write_ms);
+    let elapsed_ms = elapsed * 1000.0;
+
+    DiskDeviceMetrics {
+        name: cur.name.clone(),
+        read_bps: (delta(cur.sectors_read, prev.sectors_read) * SECTOR_BYTES as f64 / elapsed) as u64,
+        write_bps: (delta(cur.sectors_written, prev.sectors_written) * SECTOR_BYTES as f64 / elapsed) as u64,
+        read_iops: (reads / elapsed) as f32,
+        write_iops: (writes / elapsed) as f32,
+        avg_latency_ms: if reads + writes > 0.0 { (io_wait_ms / (reads + writes)) as f32 } else { 0.0 },
+        queue_depth: (delta(cur.weighted_io_ms, prev.weighted_io_ms) / elapsed_ms) as f32,
+        busy_pct: (delta(cur.io_ms, prev.io_ms) / elapsed_ms * 100.0).min(100.0) as f32,
+        read_bytes_total: cur.sectors_read * SECTOR_BYTES,
+        write_bytes_total: cur.sectors_written * SECTOR_BYTES,
+        reads_total: cur.reads,
+        writes_total: cur.writes,
+    }
+}
+
+// Disk bandwidth and per-device I/O statistics on Linux from /proc/diskstats.
+pub struct DiskstatsSource {
+    diskstats: PathBuf,
+    sys_block: PathBuf,
+    previous: HashMap<String, DiskStat>,
+    previous_at: Option<Instant>,
+    health: SourceHealth,
+}
+
+impl DiskstatsSource {
+    pub fn new() -> Self {
+        Self::with_paths("/proc/diskstats", "/sys/block")
+    }
+
+    // Lets the source run against a fake procfs/sysfs tree.
+    pub fn with_paths(diskstats: impl Into<PathBuf>, sys_block: impl Into<PathBuf>) -> Self {
+        Self {
+            diskstats: diskstats.into(),
+            sys_block: sys_block.into(),
+            previous: HashMap::new(),
+            previous_at: None,
+            health: SourceHealth::Pending,
+        }
+    }
+
+    fn read(&self) -> Result<Vec<DiskStat>, String> {
+        std::fs::read_to_string(&self.diskstats)
+            .map(|text| parse_diskstats(&text))
+            .map_err(|e| format!("{}: {}", self.diskstats.display(), e))
+    }
+}
+
+impl MetricSource for DiskstatsSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "disk", description: "Disk bandwidth (/proc/diskstats)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        match self.read() {
+            Ok(stats) => {
+                self.previous = stats.into_iter().map(|s| (s.name.clone(), s)).collect();
+                self.previous_at = Some(Instant::now());
+                self.health = SourceHealth::Ok;
+                Ok(())
+            }
+            Err(e) => {
+                self.health = SourceHealth::Unavailable(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let stats = match self.read() {
+            Ok(stats) => stats,
+            Err(e) => {
+                self.health = SourceHealth::Degraded(e);
+                return;
+            }
+        };
+        let now = Instant::now();
+        let elapsed = self.previous_at.map(|t| now.duration_since(t).as_secs_f64()).unwrap_or(0.0);
+
+        if elapsed > 0.0 {
+            m.disks = stats
+                .iter()
+                .filter(|s| is_physical_disk(&self.sys_block, &s.name))
+                // A device that appeared since the last sample has no baseline yet.
+                .filter_map(|s| self.previous.get(&s.name).map(|prev| device_rates(prev, s, elapsed)))
+                .collect();
+            m.disks.sort_by(|a, b| a.name.cmp(&b.name));
+            m.disk_read_bps = m.disks.iter().map(|d| d.read_bps).sum();
+            m.disk_write_bps = m.disks.iter().map(|d| d.write_bps).sum();
+        }
+
+        self.previous = stats.into_iter().map(|s| (s.name.clone(), s)).collect();
+        self.previous_at = Some(now);
+        self.health = SourceHealth::Ok;
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    // From a 6.x kernel: 20 fields, with discard and flush counters at the end.
+    const DISKSTATS: &str = "\
+ 259       0 nvme0n1 52349 1045 6015822 10327 81205 48921 9262408 98112 0 54880 113210 0 0 0 0 4121 4770
+ 259       1 nvme0n1p1 310 0 18842 61 2 0 2 0 0 80 61 0 0 0 0 0 0
+   7       0 loop0 48 0 2158 9 0 0 0 0 0 28 9 0 0 0 0 0 0
+   8       0 sda 900 10 20480 400 100 0 8192 300 0 500 700
+ 253       0 dm-0 garbage
+";
+
+    fn stat(name: &str, reads: u64, sectors_read: u64, io_ms: u64) -> DiskStat {
+        DiskStat {
+            name: name.to_string(),
+            reads,
+            sectors_read,
+            read_ms: reads,
+            writes: 0,
+            sectors_written: 0,
+            write_ms: 0,
+            io_ms,
+            weighted_io_ms: io_ms * 2,
+        }
+    }
+
+    #[test]
+    fn parses_old_and_new_layouts() {
+        let stats = parse_diskstats(DISKSTATS);
+        let names: Vec<&str> = stats.iter().map(|s| s.name.as_str()).collect();
+        // dm-0 is malformed and dropped; the 14-field sda line is an older kernel.
+        assert_eq!(names, ["nvme0n1", "nvme0n1p1", "loop0", "sda"]);
+        assert_eq!(
+            stats[0],
+            DiskStat {
+                name: "nvme0n1".to_string(),
+                reads: 52349,
+                sectors_read: 6015822,
+                read_ms: 10327,
+                writes: 81205,
+                sectors_written: 9262408,
+                write_ms: 98112,
+                io_ms: 54880,
+                weighted_io_ms: 113210,
+            }
+        );
+        assert_eq!(stats[3].sectors_written, 8192);
+    }
+
+    #[test]
+    fn rates_like_iostat() {
+        let prev = stat("sda", 100, 2048, 1000);
+        let cur = stat("sda", 300, 6144, 1500);
+        let d = device_rates(&prev, &cur, 2.0);
+        assert_eq!(d.read_bps, 4096 * 512 / 2);
+        assert_eq!(d.read_iops, 100.0);
+        assert_eq!(d.avg_latency_ms, 1.0);
+        assert_eq!(d.busy_pct, 25.0);
+        assert_eq!(d.queue_depth, 0.5);
+        assert_eq!(d.read_bytes_total, 6144 * 512);
+    }
+
+    #[test]
+    fn counter_reset_gives_zero_not_a_spike() {
+        // The device was removed and re-added between the samples.
+        let prev = stat("sda", 5000, 900_000, 8000);
+        let cur = stat("sda", 10, 64, 5);
+        let d = device_rates(&prev, &cur, 1.0);
+        assert_eq!((d.read_bps, d.read_iops, d.busy_pct), (0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn source_reports_physical_disks_only() {
+        let tree = TempTree::new();
+        let diskstats = tree.write("proc/diskstats", DISKSTATS);
+        tree.mkdir("sys/block/nvme0n1/device");
+        tree.mkdir("sys/block/sda/device");
+        // loop devices have a /sys/block entry but no backing device.
+        tree.mkdir("sys/block/loop0");
+
+        let mut source = DiskstatsSource::with_paths(&diskstats, tree.path("sys/block"));
+        source.init().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        // sda goes away, nvme0n1 moves on.
+        tree.write(
+            "proc/diskstats",
+            " 259       0 nvme0n1 52449 1045 6025822 10427 81205 48921 9262408 98112 0 54900 113230\n",
+        );
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+
+        let names: Vec<&str> = m.disks.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["nvme0n1"]);
+        assert!(m.disks[0].read_bps > 0);
+        assert_eq!(m.disk_read_bps, m.disks[0].read_bps);
+        assert_eq!(source.health(), SourceHealth::Ok);
+
+        std::fs::remove_file(&diskstats).unwrap();
+        source.sample(&mut m);
+        assert!(matches!(source.health(), SourceHealth::Degraded(_)));
+    }
+}
@@ -0,0 +1,223 @@
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{GpuMetrics, HardwareMetrics, PcieLink, ThrottleReason};
+
+// What identifies a card across samples and reboots.
+pub struct GpuIdentity {
+    pub name: String,
+    pub uuid: String,
+    pub pci_bus_id: String,
+}
+
+// Where GpuSource gets its devices from: NVML on real machines, the mock in
+// tests and mock-gpu builds. Each query is separate so a card that
+// doesn't support one (power on many consumer boards, for instance) still
+// reports the rest.
+pub trait GpuBackend: Send {
+    fn description(&self) -> &'static str;
+    fn init(&mut self) -> Result<(), String>;
+    // Called once at the start of every sample, before any query.
+    fn refresh(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+    fn device_count(&self) -> Result<u32, String>;
+    fn identity(&self, index: u32) -> Result<GpuIdentity, String>;
+    // (used, total) in bytes.
+    fn memory(&self, index: u32) -> Result<(u64, u64), String>;
+    // (tx, rx) in bytes/s.
+    fn pcie_throughput(&self, index: u32) -> Result<(u64, u64), String>;
+    // (SM, memory controller) busy in %.
+    fn utilization(&self, index: u32) -> Result<(u32, u32), String>;
+    // (graphics, memory) in MHz.
+    fn clocks(&self, index: u32) -> Result<(u32, u32), String>;
+    // Highest (graphics, memory) clocks the card can run at, in MHz.
+    fn max_clocks(&self, index: u32) -> Result<(u32, u32), String>;
+    // Core temperature in °C.
+    fn temperature(&self, index: u32) -> Result<u32, String>;
+    // (draw, enforced limit) in W.
+    fn power(&self, index: u32) -> Result<(f32, f32), String>;
+    fn pcie_link(&self, index: u32) -> Result<PcieLink, String>;
+    fn throttle_reasons(&self, index: u32) -> Result<Vec<ThrottleReason>, String>;
+}
+
+// Queries every metric of one device. Only a missing identity fails the
+// device; anything else the card doesn't support is left empty.
+pub fn read_device(backend: &dyn GpuBackend, index: u32) -> Result<GpuMetrics, String> {
+    let identity = backend.identity(index)?;
+    let mut gpu = GpuMetrics {
+        index,
+        name: identity.name,
+        uuid: identity.uuid,
+        pci_bus_id: identity.pci_bus_id,
+        ..Default::default()
+    };
+    if let Ok((used, total)) = backend.memory(index) {
+        gpu.vram_used_mb = used / 1024 / 1024;
+        gpu.vram_total_mb = total / 1024 / 1024;
+    }
+    if let Ok((tx, rx)) = backend.pcie_throughput(index) {
+        gpu.pcie_tx = tx;
+        gpu.pcie_rx = rx;
+    }
+    if let Ok((sm, memory)) = backend.utilization(index) {
+        gpu.util_pct = Some(sm);
+        gpu.mem_util_pct = Some(memory);
+    }
+    if let Ok((graphics, memory)) = backend.clocks(index) {
+        gpu.graphics_clock_mhz = Some(graphics);
+        gpu.memory_clock_mhz = Some(memory);
+    }
+    if let Ok((graphics, memory)) = backend.max_clocks(index) {
+        gpu.max_graphics_clock_mhz = Some(graphics);
+        gpu.max_memory_clock_mhz = Some(memory);
+    }
+    gpu.temperature_c = backend.temperature(index).ok();
+    if let Ok((draw, limit)) = backend.power(index) {
+        gpu.power_w = Some(draw);
+        gpu.power_limit_w = Some(limit);
+    }
+    gpu.pcie_link = backend.pcie_link(index).ok();
+    gpu.throttle_reasons = backend.throttle_reasons(index).unwrap_or_default();
+    Ok(gpu)
+}
+
+// Per-GPU metrics for every device the backend reports, or only the ones
+// listed in `indices`.
+pub struct GpuSource {
+    backend: Box<dyn GpuBackend>,
+    indices: Vec<u32>,
+    health: SourceHealth,
+}
+
+impl GpuSource {
+    // An empty `indices` means all devices.
+    pub fn new(backend: Box<dyn GpuBackend>, indices: Vec<u32>) -> Self {
+        Self { backend, indices, health: SourceHealth::Pending }
+    }
+}
+
+impl MetricSource for GpuSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "gpu", description: self.backend.description() }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        match self.backend.init() {
+            Ok(()) => {
+                self.health = SourceHealth::Ok;
+                Ok(())
+            }
+            Err(e) => {
+                self.health = SourceHealth::Unavailable(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let indices = match self.backend.refresh() {
+            Err(e) => Err(e),
+            Ok(()) if self.indices.is_empty() => self.backend.device_count().map(|count| (0..count).collect()),
+            Ok(()) => Ok(self.indices.clone()),
+        };
+        let indices: Vec<u32> = match indices {
+            Ok(indices) => indices,
+            Err(e) => {
+                self.health = SourceHealth::Degraded(e);
+                return;
+            }
+        };
+
+        // One card failing (fallen off the bus, reset in progress) shouldn't
+        // hide the others.
+        let mut errors = Vec::new();
+        m.gpus = indices
+            .into_iter()
+            .filter_map(|index| {
+                read_device(self.backend.as_ref(), index).map_err(|e| errors.push(format!("GPU {}: {}", index, e))).ok()
+            })
+            .collect();
+
+        m.gpu_name = match m.gpus.as_slice() {
+            [] => "No GPU".to_string(),
+            [gpu] => gpu.name.clone(),
+            gpus => format!("{} GPUs", gpus.len()),
+        };
+        m.gpu_vram_used_mb = m.gpus.iter().map(|g| g.vram_used_mb).sum();
+        m.gpu_vram_total_mb = m.gpus.iter().map(|g| g.vram_total_mb).sum();
+        m.gpu_pcie_tx = m.gpus.iter().map(|g| g.pcie_tx).sum();
+        m.gpu_pcie_rx = m.gpus.iter().map(|g| g.pcie_rx).sum();
+        let utils: Vec<u32> = m.gpus.iter().filter_map(|g| g.util_pct).collect();
+        m.gpu_util_pct = if utils.is_empty() { 0.0 } else { utils.iter().sum::<u32>() as f32 / utils.len() as f32 };
+
+        self.health = if errors.is_empty() { SourceHealth::Ok } else { SourceHealth::Degraded(errors.join("; ")) };
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::MockGpuBackend;
+
+    fn card(name: &str, vram_used_mb: u64, pcie_rx: u64, util_pct: u32) -> GpuMetrics {
+        GpuMetrics {
+            name: name.to_string(),
+            vram_used_mb,
+            vram_total_mb: 24 * 1024,
+            pcie_tx: pcie_rx / 4,
+            pcie_rx,
+            util_pct: Some(util_pct),
+            ..Default::default()
+        }
+    }
+
+    fn source(frames: Vec<Vec<GpuMetrics>>, indices: Vec<u32>) -> GpuSource {
+        let mut source = GpuSource::new(Box::new(MockGpuBackend::scripted(frames)), indices);
+        source.init().unwrap();
+        source
+    }
+
+    #[test]
+    fn sums_memory_and_pcie_across_cards() {
+        let mut source = source(vec![vec![card("A", 1000, 8_000_000_000, 90), card("B", 3000, 4_000_000_000, 30)]], Vec::new());
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+        assert_eq!(m.gpus.iter().map(|g| g.index).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(m.gpu_name, "2 GPUs");
+        assert_eq!((m.gpu_vram_used_mb, m.gpu_vram_total_mb), (4000, 48 * 1024));
+        assert_eq!((m.gpu_pcie_rx, m.gpu_pcie_tx), (12_000_000_000, 3_000_000_000));
+        assert_eq!(m.gpu_util_pct, 60.0);
+        assert_eq!(source.health(), SourceHealth::Ok);
+    }
+
+    #[test]
+    fn only_listed_indices_are_read() {
+        let mut source = source(vec![vec![card("A", 1000, 100, 10), card("B", 3000, 200, 20), card("C", 5000, 400, 40)]], vec![2, 0]);
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+        assert_eq!(m.gpus.iter().map(|g| g.name.as_str()).collect::<Vec<_>>(), ["C", "A"]);
+        assert_eq!(m.gpu_vram_used_mb, 6000);
+        assert_eq!(m.gpu_pcie_rx, 500);
+    }
+
+    #[test]
+    fn failing_card_degrades_but_others_still_report() {
+        // Card 1 falls off the bus after the first sample.
+        let frames = vec![vec![card("A", 1000, 100, 10), card("B", 3000, 200, 20)], vec![card("A", 1200, 300, 50)]];
+        let mut source = source(frames, vec![0, 1]);
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+        assert_eq!(source.health(), SourceHealth::Ok);
+
+        source.sample(&mut m);
+        assert_eq!(m.gpu_name, "A");
+        assert_eq!((m.gpu_vram_used_mb, m.gpu_pcie_rx, m.gpu_util_pct), (1200, 300, 50.0));
+        match source.health() {
+            SourceHealth::Degraded(e) => assert!(e.starts_with("GPU 1: "), "{}", e),
+            other => panic!("expected Degraded, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{HardwareMetrics, SensorChip, SensorKind, SensorReading};
+
+// Splits a hwmon attribute file name like "temp1_input" into the sensor's
+// kind, its sysfs name ("temp1") with the index, and the attribute ("input").
+pub fn parse_attribute(file: &str) -> Option<(SensorKind, &str, u32, &str)> {
+    let (sensor, attribute) = file.split_once('_')?;
+    let digits = sensor.find(|c: char| c.is_ascii_digit())?;
+    let kind = match &sensor[..digits] {
+        "temp" => SensorKind::Temperature,
+        "fan" => SensorKind::Fan,
+        "in" => SensorKind::Voltage,
+        "power" => SensorKind::Power,
+        _ => return None,
+    };
+    Some((kind, sensor, sensor[digits..].parse().ok()?, attribute))
+}
+
+// sysfs values are integers in millidegrees, RPM, millivolts and microwatts.
+fn scale(kind: SensorKind) -> f64 {
+    match kind {
+        SensorKind::Temperature | SensorKind::Voltage => 1e3,
+        SensorKind::Fan => 1.0,
+        SensorKind::Power => 1e6,
+    }
+}
+
+fn kind_order(kind: SensorKind) -> u8 {
+    match kind {
+        SensorKind::Temperature => 0,
+        SensorKind::Fan => 1,
+        SensorKind::Voltage => 2,
+        SensorKind::Power => 3,
+    }
+}
+
+// One hwmon chip directory, e.g. /sys/class/hwmon/hwmon2. None if it has no
+// name, which every driver is required to provide.
+pub fn read_chip(dir: &Path) -> Option<SensorChip> {
+    // Drivers from before the 3.x cleanup keep their attributes under device/.
+    let dir = if dir.join("name").exists() { dir.to_path_buf() } else { dir.join("device") };
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok().map(|text| text.trim().to_string());
+    let name = read("name")?;
+    // device links to the parent device's sysfs directory, named after it.
+    let device = std::fs::canonicalize(dir.join("device"))
+        .ok()
+        .and_then(|target| target.file_name()?.to_str().map(str::to_string))
+        .unwrap_or_default();
+
+    let mut sensors: Vec<(SensorKind, u32, String)> = std::fs::read_dir(&dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let file = entry.file_name();
+            let (kind, sensor, index, attribute) = parse_attribute(file.to_str()?)?;
+            // amdgpu and a few others only have an average for power.
+            let primary = attribute == "input" || (kind == SensorKind::Power && attribute == "average");
+            primary.then(|| (kind, index, sensor.to_string()))
+        })
+        .collect();
+    sensors.sort_by_key(|(kind, index, _)| (kind_order(*kind), *index));
+    sensors.dedup_by(|a, b| a.2 == b.2);
+
+    let readings = sensors
+        .into_iter()
+        .filter_map(|(kind, _, sensor)| {
+            let value = |attribute: &str| {
+                read(&format!("{}_{}", sensor, attribute))?.parse::<f64>().ok().map(|v| v / scale(kind))
+            };
+            // Disconnected inputs fail the read (ENODATA, EIO) instead of reading 0.
+            let value_now = value("input").or_else(|| value("average"))?;
+            Some(SensorReading {
+                kind,
+                label: read(&format!("{}_label", sensor)).unwrap_or_else(|| sensor.clone()),
+                value: value_now,
+                max: value("max").or_else(|| value("cap")),
+                crit: value("crit"),
+            })
+        })
+        .collect();
+    Some(SensorChip { name, device, sensors: readings })
+}
+
+// Temperatures, fans, voltages and power from every chip under /sys/class/hwmon.
+pub struct HwmonSource {
+    dir: PathBuf,
+    health: SourceHealth,
+}
+
+impl HwmonSource {
+    pub fn new() -> Self {
+        Self::with_path("/sys/class/hwmon")
+    }
+
+    // Lets the source run against a fake sysfs tree.
+    pub fn with_path(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), health: SourceHealth::Pending }
+    }
+
+    // Chips come and go with their drivers (amdgpu, nvme) and the hwmonN
+    // numbering with them, so the directory is listed on every sample.
+    fn read(&self) -> Result<Vec<SensorChip>, String> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| format!("{}: {}", self.dir.display(), e))?;
+        let mut chips: Vec<(u32, SensorChip)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let index = entry.file_name().to_str()?.strip_prefix("hwmon")?.parse().ok()?;
+                Some((index, read_chip(&entry.path())?))
+            })
+            .collect();
+        chips.sort_by_key(|(index, _)| *index);
+        Ok(chips.into_iter().map(|(_, chip)| chip).collect())
+    }
+}
+
+impl MetricSource for HwmonSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "sensors", description: "Hardware sensors (/sys/class/hwmon)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        // Virtual machines and containers usually have no chips at all.
+        let result = match self.read() {
+            Ok(chips) if chips.is_empty() => Err(format!("{}: no sensors", self.dir.display())),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        };
+        self.health = match &result {
+            Ok(()) => SourceHealth::Ok,
+            Err(e) => SourceHealth::Unavailable(e.clone()),
+        };
+        result
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        match self.read() {
+            Ok(chips) => {
+                m.sensors = chips;
+                self.health = SourceHealth::Ok;
+            }
+            Err(e) => self.health = SourceHealth::Degraded(e),
+        }
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    fn machine() -> TempTree {
+        let tree = TempTree::new();
+        tree.mkdir("devices/pci0000:00/0000:00:18.3");
+        tree.write("hwmon/hwmon0/name", "k10temp\n");
+        tree.symlink("hwmon/hwmon0/device", "../../devices/pci0000:00/0000:00:18.3");
+        tree.write("hwmon/hwmon0/temp1_input", "45125\n");
+        tree.write("hwmon/hwmon0/temp1_label", "Tctl\n");
+        tree.write("hwmon/hwmon0/temp1_max", "70000\n");
+        tree.write("hwmon/hwmon0/temp1_crit", "95000\n");
+        tree.write("hwmon/hwmon0/temp2_input", "30000\n");
+        // A disconnected input: listed, but the read fails.
+        tree.mkdir("hwmon/hwmon0/temp3_input");
+        tree.write("hwmon/hwmon0/temp3_label", "Tccd2\n");
+        tree.write("hwmon/hwmon0/fan1_input", "1200\n");
+        tree.write("hwmon/hwmon0/in0_input", "1234\n");
+        tree.write("hwmon/hwmon0/pwm1", "128\n");
+        // amdgpu reports power as an average only.
+        tree.write("hwmon/hwmon1/name", "amdgpu\n");
+        tree.write("hwmon/hwmon1/power1_average", "45000000\n");
+        tree.write("hwmon/hwmon1/power1_cap", "200000000\n");
+        // A pre-3.x driver with everything under device/, numbered past 9.
+        tree.write("hwmon/hwmon10/device/name", "w83627ehf\n");
+        tree.write("hwmon/hwmon10/device/temp1_input", "38000\n");
+        tree.write("hwmon/hwmon10/device/temp1_label", "SYSTIN\n");
+        // No name: not a chip.
+        tree.write("hwmon/hwmon2/temp1_input", "1000\n");
+        tree
+    }
+
+    #[test]
+    fn parses_attribute_names() {
+        assert!(parse_attribute("temp1_input") == Some((SensorKind::Temperature, "temp1", 1, "input")));
+        assert!(parse_attribute("in12_max") == Some((SensorKind::Voltage, "in12", 12, "max")));
+        assert!(parse_attribute("power1_average") == Some((SensorKind::Power, "power1", 1, "average")));
+        assert!(parse_attribute("pwm1").is_none());
+        assert!(parse_attribute("name").is_none());
+        assert!(parse_attribute("temp_input").is_none());
+        assert!(parse_attribute("curr1_input").is_none());
+    }
+
+    #[test]
+    fn scales_labels_and_skips_disconnected_inputs() {
+        let tree = machine();
+        let chip = read_chip(&tree.path("hwmon/hwmon0")).unwrap();
+        assert_eq!((chip.name.as_str(), chip.device.as_str()), ("k10temp", "0000:00:18.3"));
+        let sensors: Vec<(&str, f64)> = chip.sensors.iter().map(|s| (s.label.as_str(), s.value)).collect();
+        // Ordered by kind, then index; temp3 is left out.
+        assert_eq!(sensors, [("Tctl", 45.125), ("temp2", 30.0), ("fan1", 1200.0), ("in0", 1.234)]);
+        let tctl = &chip.sensors[0];
+        assert_eq!((tctl.max, tctl.crit), (Some(70.0), Some(95.0)));
+        assert_eq!((chip.sensors[1].max, chip.sensors[1].crit), (None, None));
+        assert!(chip.sensors[2].kind == SensorKind::Fan && chip.sensors[3].kind == SensorKind::Voltage);
+
+        let gpu = read_chip(&tree.path("hwmon/hwmon1")).unwrap();
+        assert_eq!(gpu.device, "");
+        assert!(gpu.sensors[0].kind == SensorKind::Power);
+        assert_eq!((gpu.sensors[0].value, gpu.sensors[0].max), (45.0, Some(200.0)));
+
+        assert!(read_chip(&tree.path("hwmon/hwmon2")).is_none());
+    }
+
+    #[test]
+    fn source_groups_by_chip_in_hwmon_order() {
+        let tree = machine();
+        let mut source = HwmonSource::with_path(tree.path("hwmon"));
+        source.init().unwrap();
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+        let chips: Vec<(&str, usize)> = m.sensors.iter().map(|c| (c.name.as_str(), c.sensors.len())).collect();
+        assert_eq!(chips, [("k10temp", 4), ("amdgpu", 1), ("w83627ehf", 1)]);
+        assert_eq!(m.sensors[2].sensors[0].label, "SYSTIN");
+        assert_eq!(m.sensors[2].sensors[0].value, 38.0);
+
+        let mut empty = HwmonSource::with_path(tree.path("devices"));
+        assert!(empty.init().is_err());
+    }
+}
@@ -0,0 +1,221 @@
+#[cfg(feature = "mock-gpu")]
+use std::path::Path;
+
+use super::{GpuBackend, GpuIdentity};
+use crate::metrics::{GpuMetrics, PcieLink, ThrottleReason};
+
+const VRAM_TOTAL_MB: u64 = 24 * 1024;
+
+enum Script {
+    // `count` cards with smoothly varying load, each with its own phase.
+    Generated { count: u32 },
+    // Exactly these readings, one entry per sample, looping at the end.
+    Frames(Vec<Vec<GpuMetrics>>),
+}
+
+// Fake GPUs for machines without an NVIDIA driver. Values only depend on how
+// many samples were taken, never on the clock, so a given script always
+// produces the same sequence of metrics.
+pub struct MockGpuBackend {
+    script: Script,
+    // Index of the current frame; advanced by every refresh() but the first.
+    frame: usize,
+    started: bool,
+}
+
+impl MockGpuBackend {
+    pub fn generated(count: u32) -> Self {
+        Self { script: Script::Generated { count }, frame: 0, started: false }
+    }
+
+    pub fn scripted(frames: Vec<Vec<GpuMetrics>>) -> Self {
+        Self { script: Script::Frames(frames), frame: 0, started: false }
+    }
+
+    #[cfg(feature = "mock-gpu")]
+    pub fn load_script(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let frames = parse_script(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Self::scripted(frames))
+    }
+
+    fn gpu(&self, index: u32) -> Result<GpuMetrics, String> {
+        let missing = || format!("no mock GPU with index {}", index);
+        match &self.script {
+            Script::Generated { count } => {
+                if index >= *count {
+                    return Err(missing());
+                }
+                let t = self.frame as f64 / 20.0 + index as f64;
+                let load = |offset: f64| 0.5 + 0.5 * (t + offset).sin();
+                let power = 80.0 + 270.0 * load(2.5) as f32;
+                Ok(GpuMetrics {
+                    index,
+                    name: "Mock GPU".to_string(),
+                    uuid: format!("GPU-00000000-0000-0000-0000-{:012x}", index),
+                    pci_bus_id: format!("00000000:{:02X}:00.0", 0x17 + index * 0x10),
+                    vram_used_mb: (VRAM_TOTAL_MB as f64 * (0.2 + 0.7 * load(0.0))) as u64,
+                    vram_total_mb: VRAM_TOTAL_MB,
+                    pcie_tx: (2e9 * load(1.0)) as u64,
+                    pcie_rx: (12e9 * load(2.0)) as u64,
+                    util_pct: Some((100.0 * load(2.5)) as u32),
+                    mem_util_pct: Some((60.0 * load(3.0)) as u32),
+                    graphics_clock_mhz: Some(1200 + (600.0 * load(2.5)) as u32),
+                    memory_clock_mhz: Some(9501),
+                    max_graphics_clock_mhz: Some(1800),
+                    max_memory_clock_mhz: Some(9501),
+                    temperature_c: Some(40 + (40.0 * load(2.0)) as u32),
+                    power_w: Some(power),
+                    power_limit_w: Some(350.0),
+                    // The second card sits in a slot that trained at x8.
+                    pcie_link: Some(PcieLink { gen: 4, width: if index == 1 { 8 } else { 16 }, max_gen: 4, max_width: 16 }),
+                    throttle_reasons: if power > 330.0 { vec![ThrottleReason::SwPowerCap] } else { Vec::new() },
+                })
+            }
+            Script::Frames(frames) => {
+                let frame = &frames[self.frame % frames.len()];
+                frame.get(index as usize).cloned().ok_or_else(missing)
+            }
+        }
+    }
+}
+
+// One frame per line, each a JSON array of GPU readings. Fields left out
+// of a reading are zero, or unsupported for the optional ones:
+//
+//   [{"name": "A100", "vram_total_mb": 40960, "pcie_rx": 12000000000, "util_pct": 35}]
+pub fn parse_script(text: &str) -> Result<Vec<Vec<GpuMetrics>>, String> {
+    let frames = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {}", n + 1, e)))
+        .collect::<Result<Vec<Vec<GpuMetrics>>, String>>()?;
+    if frames.is_empty() {
+        return Err("script has no frames".to_string());
+    }
+    Ok(frames)
+}
+
+fn supported<T>(value: Option<T>) -> Result<T, String> {
+    value.ok_or_else(|| "not supported".to_string())
+}
+
+impl GpuBackend for MockGpuBackend {
+    fn description(&self) -> &'static str {
+        "Mock GPUs"
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        self.frame = 0;
+        self.started = false;
+        Ok(())
+    }
+
+    // The first sample reads frame 0.
+    fn refresh(&mut self) -> Result<(), String> {
+        if self.started {
+            self.frame += 1;
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    fn device_count(&self) -> Result<u32, String> {
+        Ok(match &self.script {
+            Script::Generated { count } => *count,
+            Script::Frames(frames) => frames[self.frame % frames.len()].len() as u32,
+        })
+    }
+
+    fn identity(&self, index: u32) -> Result<GpuIdentity, String> {
+        let gpu = self.gpu(index)?;
+        Ok(GpuIdentity { name: gpu.name, uuid: gpu.uuid, pci_bus_id: gpu.pci_bus_id })
+    }
+
+    fn memory(&self, index: u32) -> Result<(u64, u64), String> {
+        let gpu = self.gpu(index)?;
+        Ok((gpu.vram_used_mb * 1024 * 1024, gpu.vram_total_mb * 1024 * 1024))
+    }
+
+    fn pcie_throughput(&self, index: u32) -> Result<(u64, u64), String> {
+        let gpu = self.gpu(index)?;
+        Ok((gpu.pcie_tx, gpu.pcie_rx))
+    }
+
+    fn utilization(&self, index: u32) -> Result<(u32, u32), String> {
+        let gpu = self.gpu(index)?;
+        Ok((supported(gpu.util_pct)?, gpu.mem_util_pct.unwrap_or(0)))
+    }
+
+    fn clocks(&self, index: u32) -> Result<(u32, u32), String> {
+        let gpu = self.gpu(index)?;
+        Ok((supported(gpu.graphics_clock_mhz)?, gpu.memory_clock_mhz.unwrap_or(0)))
+    }
+
+    fn max_clocks(&self, index: u32) -> Result<(u32, u32), String> {
+        let gpu = self.gpu(index)?;
+        Ok((supported(gpu.max_graphics_clock_mhz)?, gpu.max_memory_clock_mhz.unwrap_or(0)))
+    }
+
+    fn temperature(&self, index: u32) -> Result<u32, String> {
+        supported(self.gpu(index)?.temperature_c)
+    }
+
+    fn power(&self, index: u32) -> Result<(f32, f32), String> {
+        let gpu = self.gpu(index)?;
+        Ok((supported(gpu.power_w)?, gpu.power_limit_w.unwrap_or(0.0)))
+    }
+
+    fn pcie_link(&self, index: u32) -> Result<PcieLink, String> {
+        supported(self.gpu(index)?.pcie_link)
+    }
+
+    fn throttle_reasons(&self, index: u32) -> Result<Vec<ThrottleReason>, String> {
+        Ok(self.gpu(index)?.throttle_reasons)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scripts() {
+        let frames = parse_script(
+            "[{\"name\": \"A100\", \"pcie_rx\": 13000000000, \"util_pct\": 40}]\n\
+             \n\
+             [{\"name\": \"A100\"}, {\"name\": \"H100\", \"temperature_c\": 61}]\n",
+        )
+        .unwrap();
+        assert_eq!(frames.iter().map(Vec::len).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!((frames[0][0].pcie_rx, frames[0][0].util_pct), (13_000_000_000, Some(40)));
+        assert_eq!((frames[1][0].util_pct, frames[1][1].temperature_c), (None, Some(61)));
+
+        let Err(e) = parse_script("[]\n\n[{\"name\": 3}]\n") else { panic!("script accepted") };
+        assert!(e.starts_with("line 3: "), "{}", e);
+        assert_eq!(parse_script("\n  \n").err().as_deref(), Some("script has no frames"));
+    }
+
+    #[test]
+    fn generated_cards_repeat_and_the_second_runs_at_x8() {
+        let mut a = MockGpuBackend::generated(2);
+        let mut b = MockGpuBackend::generated(2);
+        for _ in 0..5 {
+            a.refresh().unwrap();
+            b.refresh().unwrap();
+        }
+        assert_eq!(a.device_count(), Ok(2));
+        assert_eq!(a.pcie_throughput(0), b.pcie_throughput(0));
+        assert_eq!(a.pcie_link(0).map(|l| l.width), Ok(16));
+        assert_eq!(a.pcie_link(1).map(|l| l.width), Ok(8));
+        assert!(a.identity(2).is_err());
+
+        // init() starts the sequence over.
+        a.init().unwrap();
+        a.refresh().unwrap();
+        let mut fresh = MockGpuBackend::generated(2);
+        fresh.refresh().unwrap();
+        assert_eq!(a.memory(1), fresh.memory(1));
+    }
+}
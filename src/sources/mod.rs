@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::SourcesConfig;
+use crate::metrics::HardwareMetrics;
+
+#[cfg(target_os = "linux")]
+mod cgroup;
+#[cfg(not(target_os = "linux"))]
+mod cpu;
+#[cfg(windows)]
+mod disk;
+#[cfg(target_os = "linux")]
+mod diskstats;
+#[cfg(any(test, feature = "nvidia", feature = "mock-gpu"))]
+mod gpu;
+#[cfg(target_os = "linux")]
+mod hwmon;
+#[cfg(any(test, feature = "mock-gpu"))]
+mod mock_gpu;
+#[cfg(not(target_os = "linux"))]
+mod net;
+#[cfg(target_os = "linux")]
+mod netdev;
+#[cfg(target_os = "linux")]
+mod nvme;
+#[cfg(feature = "nvidia")]
+mod nvml;
+#[cfg(not(target_os = "linux"))]
+mod processes;
+#[cfg(target_os = "linux")]
+mod procpid;
+#[cfg(target_os = "linux")]
+mod procstat;
+#[cfg(target_os = "linux")]
+mod psi;
+#[cfg(target_os = "linux")]
+mod rapl;
+
+#[cfg(target_os = "linux")]
+pub use cgroup::CgroupSource;
+#[cfg(not(target_os = "linux"))]
+pub use cpu::CpuRamSource;
+#[cfg(windows)]
+pub use disk::PdhDiskSource;
+#[cfg(target_os = "linux")]
+pub use diskstats::DiskstatsSource;
+#[cfg(any(test, feature = "nvidia", feature = "mock-gpu"))]
+pub use gpu::{GpuBackend, GpuIdentity, GpuSource};
+#[cfg(target_os = "linux")]
+pub use hwmon::HwmonSource;
+#[cfg(any(test, feature = "mock-gpu"))]
+pub use mock_gpu::MockGpuBackend;
+#[cfg(not(target_os = "linux"))]
+pub use net::NetworksSource;
+#[cfg(target_os = "linux")]
+pub use netdev::NetDevSource;
+#[cfg(target_os = "linux")]
+pub use nvme::NvmeSource;
+#[cfg(feature = "nvidia")]
+pub use nvml::NvmlBackend;
+#[cfg(not(target_os = "linux"))]
+pub use processes::ProcessesSource;
+#[cfg(target_os = "linux")]
+pub use procpid::ProcPidSource;
+#[cfg(target_os = "linux")]
+pub use procstat::ProcStatSource;
+#[cfg(target_os = "linux")]
+pub use psi::PsiSource;
+#[cfg(target_os = "linux")]
+pub use rapl::RaplSource;
+
+// Static description of a source. `id` is the stable key used to enable or
+// disable a source; `description` is what we show to the user.
+#[derive(Clone, Copy)]
+pub struct SourceInfo {
+    pub id: &'static str,
+    pub description: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SourceHealth {
+    // init() has not been called yet.
+    Pending,
+    Ok,
+    // The backend is missing on this machine (no driver, wrong OS, ...).
+    Unavailable(String),
+    // The backend initialised but the last sample failed.
+    Degraded(String),
+}
+
+// A single backend that knows how to fill in part of HardwareMetrics.
+//
+// Sources are created on the GUI thread and then moved into the polling
+// thread, hence the Send bound. init() is called once from the polling
+// thread before the first sample(); a source whose init() fails is never
+// sampled.
+pub trait MetricSource: Send {
+    fn describe(&self) -> SourceInfo;
+    fn init(&mut self) -> Result<(), String>;
+    fn sample(&mut self, metrics: &mut HardwareMetrics);
+    fn health(&self) -> SourceHealth;
+}
+
+// Snapshot of a source's state that travels with every sample. Owns its
+// strings so recordings can be loaded back.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SourceStatus {
+    pub id: String,
+    pub description: String,
+    pub enabled: bool,
+    pub health: SourceHealth,
+}
+
+struct Registered {
+    source: Box<dyn MetricSource>,
+    enabled: bool,
+    ready: bool,
+    // Why init() failed, for sources that don't record it in their health.
+    init_error: Option<String>,
+}
+
+// Owns every registered source and drives them in registration order.
+pub struct Collector {
+    sources: Vec<Registered>,
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Self { sources: Vec::new() }
+    }
+
+    // The collector the app ships with: every built-in source this build and
+    // platform supports, with the ones listed in `config.disabled` switched off.
+    pub fn with_default_sources(config: &SourcesConfig) -> Self {
+        let mut collector = Self::new();
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(ProcStatSource::new()));
+        #[cfg(not(target_os = "linux"))]
+        collector.register(Box::new(CpuRamSource::new()));
+        #[cfg(feature = "mock-gpu")]
+        let mocked = collector.register_mock_gpus(config);
+        #[cfg(not(feature = "mock-gpu"))]
+        let mocked = false;
+        if !mocked {
+            #[cfg(feature = "nvidia")]
+            collector.register(Box::new(GpuSource::new(Box::new(NvmlBackend::new()), config.gpus.clone())));
+        }
+        #[cfg(windows)]
+        collector.register(Box::new(PdhDiskSource::new()));
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(DiskstatsSource::new()));
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(NetDevSource::new()));
+        #[cfg(not(target_os = "linux"))]
+        collector.register(Box::new(NetworksSource::new()));
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(ProcPidSource::new()));
+        #[cfg(not(target_os = "linux"))]
+        collector.register(Box::new(ProcessesSource::new()));
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(CgroupSource::new()));
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(PsiSource::new()));
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(HwmonSource::new()));
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(RaplSource::new()));
+        #[cfg(target_os = "linux")]
+        collector.register(Box::new(NvmeSource::new()));
+
+        for id in collector.disable(&config.disabled) {
+            eprintln!("unknown source '{}'", id);
+        }
+        collector
+    }
+
+    // Fake GPUs in place of NVML, if the config asks for them. True if it
+    // did, even when the script failed to load.
+    #[cfg(feature = "mock-gpu")]
+    fn register_mock_gpus(&mut self, config: &SourcesConfig) -> bool {
+        let backend = if let Some(path) = &config.mock_gpu_script {
+            match MockGpuBackend::load_script(path) {
+                Ok(backend) => backend,
+                Err(e) => {
+                    eprintln!("mock GPU script: {}", e);
+                    return true;
+                }
+            }
+        } else if config.mock_gpus > 0 {
+            MockGpuBackend::generated(config.mock_gpus)
+        } else {
+            return false;
+        };
+        self.register(Box::new(GpuSource::new(Box::new(backend), config.gpus.clone())));
+        true
+    }
+
+    pub fn register(&mut self, source: Box<dyn MetricSource>) {
+        self.sources.push(Registered { source, enabled: true, ready: false, init_error: None });
+    }
+
+    // Switches off every source listed (--disable and [sources] disabled) and
+    // returns the ids that matched none.
+    pub fn disable<'a>(&mut self, ids: &'a [String]) -> Vec<&'a str> {
+        ids.iter().filter(|id| !self.set_enabled(id, false)).map(String::as_str).collect()
+    }
+
+    // Applies to every source registered under `id` (platform alternatives
+    // share an id). Returns false if there is none.
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for r in self.sources.iter_mut().filter(|r| r.source.describe().id == id) {
+            r.enabled = enabled;
+            found = true;
+        }
+        found
+    }
+
+    pub fn init(&mut self) {
+        for r in self.sources.iter_mut().filter(|r| r.enabled && !r.ready) {
+            match r.source.init() {
+                Ok(()) => {
+                    r.ready = true;
+                    r.init_error = None;
+                }
+                Err(e) => r.init_error = Some(e),
+            }
+        }
+    }
+
+    pub fn sample(&mut self, metrics: &mut HardwareMetrics) {
+        for r in self.sources.iter_mut().filter(|r| r.enabled && r.ready) {
+            r.source.sample(metrics);
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<SourceStatus> {
+        self.sources
+            .iter()
+            .map(|r| SourceStatus {
+                id: r.source.describe().id.to_string(),
+                description: r.source.describe().description.to_string(),
+                enabled: r.enabled,
+                health: match (&r.init_error, r.source.health()) {
+                    (Some(e), SourceHealth::Pending | SourceHealth::Ok) => SourceHealth::Unavailable(e.clone()),
+                    (_, health) => health,
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Appends its id to gpu_name when sampled, so tests can see which
+    // sources ran and in what order.
+    struct Stub {
+        id: &'static str,
+        init: Result<(), String>,
+        health: SourceHealth,
+    }
+
+    impl Stub {
+        fn boxed(id: &'static str, init: Result<(), String>) -> Box<dyn MetricSource> {
+            Box::new(Stub { id, init, health: SourceHealth::Pending })
+        }
+    }
+
+    impl MetricSource for Stub {
+        fn describe(&self) -> SourceInfo {
+            SourceInfo { id: self.id, description: "stub" }
+        }
+
+        fn init(&mut self) -> Result<(), String> {
+            // Deliberately leaves the health Pending on failure.
+            if self.init.is_ok() {
+                self.health = SourceHealth::Ok;
+            }
+            self.init.clone()
+        }
+
+        fn sample(&mut self, metrics: &mut HardwareMetrics) {
+            metrics.gpu_name.push_str(self.id);
+        }
+
+        fn health(&self) -> SourceHealth {
+            self.health.clone()
+        }
+    }
+
+    fn sampled(collector: &mut Collector) -> String {
+        let mut m = HardwareMetrics { gpu_name: String::new(), ..Default::default() };
+        collector.sample(&mut m);
+        m.gpu_name
+    }
+
+    #[test]
+    fn failed_init_is_unavailable_and_never_sampled() {
+        let mut collector = Collector::new();
+        collector.register(Stub::boxed("a", Ok(())));
+        collector.register(Stub::boxed("b", Err("no device".to_string())));
+        collector.init();
+
+        assert_eq!(sampled(&mut collector), "a");
+        let statuses = collector.statuses();
+        assert_eq!(statuses[0].health, SourceHealth::Ok);
+        assert_eq!(statuses[1].health, SourceHealth::Unavailable("no device".to_string()));
+    }
+
+    #[test]
+    fn disabled_sources_are_skipped() {
+        let mut collector = Collector::new();
+        collector.register(Stub::boxed("cpu", Ok(())));
+        collector.register(Stub::boxed("gpu", Ok(())));
+        // Platform alternatives share an id; disabling one disables both.
+        collector.register(Stub::boxed("gpu", Ok(())));
+        collector.register(Stub::boxed("net", Ok(())));
+
+        let ids = vec!["gpu".to_string(), "nope".to_string()];
+        assert_eq!(collector.disable(&ids), vec!["nope"]);
+        collector.init();
+
+        assert_eq!(sampled(&mut collector), "cpunet");
+        let enabled: Vec<bool> = collector.statuses().iter().map(|s| s.enabled).collect();
+        assert_eq!(enabled, [true, false, false, true]);
+        // Never initialised, so still pending rather than failed.
+        assert_eq!(collector.statuses()[1].health, SourceHealth::Pending);
+    }
+
+    #[test]
+    fn statuses_follow_registration_order() {
+        let mut collector = Collector::new();
+        for id in ["procs", "cpu", "disk"] {
+            collector.register(Stub::boxed(id, Ok(())));
+        }
+        collector.init();
+
+        let ids: Vec<String> = collector.statuses().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, ["procs", "cpu", "disk"]);
+        assert_eq!(sampled(&mut collector), "procscpudisk");
+    }
+}
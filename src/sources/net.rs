@@ -0,0 +1,69 @@
+use std::time::Instant;
+
+use sysinfo::Networks;
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{HardwareMetrics, NetInterfaceMetrics};
+
+// Per-interface network bandwidth via sysinfo, for platforms without a
+// procfs source. sysinfo reports neither drops nor link speed.
+pub struct NetworksSource {
+    networks: Option<Networks>,
+    last_refresh: Option<Instant>,
+}
+
+impl NetworksSource {
+    pub fn new() -> Self {
+        Self { networks: None, last_refresh: None }
+    }
+}
+
+impl MetricSource for NetworksSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "net", description: "Network (sysinfo)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        self.networks = Some(Networks::new_with_refreshed_list());
+        self.last_refresh = Some(Instant::now());
+        Ok(())
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let Some(networks) = self.networks.as_mut() else { return };
+        networks.refresh(true);
+        let now = Instant::now();
+        let elapsed = self.last_refresh.map(|t| now.duration_since(t).as_secs_f64()).unwrap_or(0.0);
+        self.last_refresh = Some(now);
+        if elapsed <= 0.0 {
+            return;
+        }
+
+        // received()/transmitted() are deltas since the previous refresh.
+        m.interfaces = networks
+            .list()
+            .iter()
+            .map(|(name, data)| NetInterfaceMetrics {
+                name: name.clone(),
+                rx_bps: (data.received() as f64 / elapsed) as u64,
+                tx_bps: (data.transmitted() as f64 / elapsed) as u64,
+                rx_pps: (data.packets_received() as f64 / elapsed) as f32,
+                tx_pps: (data.packets_transmitted() as f64 / elapsed) as f32,
+                rx_bytes_total: data.total_received(),
+                tx_bytes_total: data.total_transmitted(),
+                rx_packets_total: data.total_packets_received(),
+                tx_packets_total: data.total_packets_transmitted(),
+                rx_errors_total: data.total_errors_on_received(),
+                tx_errors_total: data.total_errors_on_transmitted(),
+                ..Default::default()
+            })
+            .collect();
+        m.interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        m.net_rx_bps = m.interfaces.iter().map(|i| i.rx_bps).sum();
+        m.net_tx_bps = m.interfaces.iter().map(|i| i.tx_bps).sum();
+    }
+
+    fn health(&self) -> SourceHealth {
+        if self.networks.is_some() { SourceHealth::Ok } else { SourceHealth::Pending }
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{HardwareMetrics, NetInterfaceMetrics};
+
+// Cumulative counters of one interface from /proc/net/dev.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetDevStat {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_drops: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_drops: u64,
+}
+
+// Parses the contents of /proc/net/dev, skipping the two header lines and
+// anything malformed.
+pub fn parse_net_dev(text: &str) -> Vec<NetDevStat> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let fields: Vec<u64> = rest.split_whitespace().map(|f| f.parse().ok()).collect::<Option<_>>()?;
+            if fields.len() < 16 {
+                return None;
+            }
+            Some(NetDevStat {
+                name: name.trim().to_string(),
+                rx_bytes: fields[0],
+                rx_packets: fields[1],
+                rx_errors: fields[2],
+                rx_drops: fields[3],
+                tx_bytes: fields[8],
+                tx_packets: fields[9],
+                tx_errors: fields[10],
+                tx_drops: fields[11],
+            })
+        })
+        .collect()
+}
+
+// Interfaces backed by hardware have a `device` link in sysfs; loopback,
+// bridges, veths, tunnels and bonds don't.
+pub fn is_physical_interface(sys_net: &Path, name: &str) -> bool {
+    sys_net.join(name).join("device").exists()
+}
+
+// Negotiated link speed in Mb/s. The kernel reports -1 or fails the read
+// while the link is down or the driver doesn't know.
+pub fn link_speed_mbps(sys_net: &Path, name: &str) -> u32 {
+    std::fs::read_to_string(sys_net.join(name).join("speed"))
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .filter(|&speed| speed > 0)
+        .map_or(0, |speed| speed as u32)
+}
+
+pub fn interface_rates(prev: &NetDevStat, cur: &NetDevStat, elapsed: f64) -> NetInterfaceMetrics {
+    // Counters only go backwards when the interface is recreated.
+    let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / elapsed;
+    NetInterfaceMetrics {
+        name: cur.name.clone(),
+        rx_bps: rate(cur.rx_bytes, prev.rx_bytes) as u64,
+        tx_bps: rate(cur.tx_bytes, prev.tx_bytes) as u64,
+        rx_pps: rate(cur.rx_packets, prev.rx_packets) as f32,
+        tx_pps: rate(cur.tx_packets, prev.tx_packets) as f32,
+        link_speed_mbps: 0,
+        rx_bytes_total: cur.rx_bytes,
+        tx_bytes_total: cur.tx_bytes,
+        rx_packets_total: cur.rx_packets,
+        tx_packets_total: cur.tx_packets,
+        rx_errors_total: cur.rx_errors,
+        tx_errors_total: cur.tx_errors,
+        rx_drops_total: cur.rx_drops,
+        tx_drops_total: cur.tx_drops,
+    }
+}
+
+// Per-interface network bandwidth on Linux from /proc/net/dev and /sys/class/net.
+pub struct NetDevSource {
+    net_dev: PathBuf,
+    sys_net: PathBuf,
+    previous: HashMap<String, NetDevStat>,
+    previous_at: Option<Instant>,
+    health: SourceHealth,
+}
+
+impl NetDevSource {
+    pub fn new() -> Self {
+        Self::with_paths("/proc/net/dev", "/sys/class/net")
+    }
+
+    // Lets the source run against a fake procfs/sysfs tree.
+    pub fn with_paths(net_dev: impl Into<PathBuf>, sys_net: impl Into<PathBuf>) -> Self {
+        Self {
+            net_dev: net_dev.into(),
+            sys_net: sys_net.into(),
+            previous: HashMap::new(),
+            previous_at: None,
+            health: SourceHealth::Pending,
+        }
+    }
+
+    fn read(&self) -> Result<Vec<NetDevStat>, String> {
+        std::fs::read_to_string(&self.net_dev)
+            .map(|text| parse_net_dev(&text))
+            .map_err(|e| format!("{}: {}", self.net_dev.display(), e))
+    }
+}
+
+impl MetricSource for NetDevSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "net", description: "Network (/proc/net/dev)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        match self.read() {
+            Ok(stats) => {
+                self.previous = stats.into_iter().map(|s| (s.name.clone(), s)).collect();
+                self.previous_at = Some(Instant::now());
+                self.health = SourceHealth::Ok;
+                Ok(())
+            }
+            Err(e) => {
+                self.health = SourceHealth::Unavailable(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let stats = match self.read() {
+            Ok(stats) => stats,
+            Err(e) => {
+                self.health = SourceHealth::Degraded(e);
+                return;
+            }
+        };
+        let now = Instant::now();
+        let elapsed = self.previous_at.map(|t| now.duration_since(t).as_secs_f64()).unwrap_or(0.0);
+
+        if elapsed > 0.0 {
+            // Virtual interfaces would count the same traffic twice (a bond and
+            // its slaves, a bridge and its veths), so only NICs are shown. In a
+            // container there are none and the virtual ones are all we have.
+            let any_physical = stats.iter().any(|s| is_physical_interface(&self.sys_net, &s.name));
+            m.interfaces = stats
+                .iter()
+                .filter(|s| s.name != "lo")
+                .filter(|s| !any_physical || is_physical_interface(&self.sys_net, &s.name))
+                .filter_map(|s| self.previous.get(&s.name).map(|prev| interface_rates(prev, s, elapsed)))
+                .map(|iface| NetInterfaceMetrics { link_speed_mbps: link_speed_mbps(&self.sys_net, &iface.name), ..iface })
+                .collect();
+            m.interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+            m.net_rx_bps = m.interfaces.iter().map(|i| i.rx_bps).sum();
+            m.net_tx_bps = m.interfaces.iter().map(|i| i.tx_bps).sum();
+        }
+
+        self.previous = stats.into_iter().map(|s| (s.name.clone(), s)).collect();
+        self.previous_at = Some(now);
+        self.health = SourceHealth::Ok;
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    const NET_DEV: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 1843302   12034    0    0    0     0          0         0  1843302   12034    0    0    0     0       0          0
+enp5s0: 9817236512 7412201  3   12    0     0          0     21093 612355821 3120488    0    1    0     0       0          0
+docker0: 52100     410    0    0    0     0          0         0    88213     502    0    0    0     0       0          0
+ wlan0: 100 2
+";
+
+    fn stat(name: &str, rx_bytes: u64, tx_bytes: u64) -> NetDevStat {
+        NetDevStat { name: name.to_string(), rx_bytes, tx_bytes, rx_packets: rx_bytes / 1000, ..Default::default() }
+    }
+
+    #[test]
+    fn parses_interfaces_and_skips_headers() {
+        let stats = parse_net_dev(NET_DEV);
+        let names: Vec<&str> = stats.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["lo", "enp5s0", "docker0"]);
+        assert_eq!(
+            stats[1],
+            NetDevStat {
+                name: "enp5s0".to_string(),
+                rx_bytes: 9817236512,
+                rx_packets: 7412201,
+                rx_errors: 3,
+                rx_drops: 12,
+                tx_bytes: 612355821,
+                tx_packets: 3120488,
+                tx_errors: 0,
+                tx_drops: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn rates_per_second() {
+        let d = interface_rates(&stat("eth0", 1_000_000, 0), &stat("eth0", 3_000_000, 500_000), 2.0);
+        assert_eq!((d.rx_bps, d.tx_bps, d.rx_pps), (1_000_000, 250_000, 1000.0));
+        assert_eq!(d.rx_bytes_total, 3_000_000);
+    }
+
+    #[test]
+    fn counter_reset_gives_zero_not_a_spike() {
+        // The interface was deleted and recreated between the samples.
+        let d = interface_rates(&stat("veth0", 9_000_000, 9_000_000), &stat("veth0", 1200, 800), 1.0);
+        assert_eq!((d.rx_bps, d.tx_bps, d.rx_pps), (0, 0, 0.0));
+    }
+
+    #[test]
+    fn source_reports_physical_interfaces_only() {
+        let tree = TempTree::new();
+        let net_dev = tree.write("proc/net/dev", NET_DEV);
+        tree.mkdir("sys/class/net/enp5s0/device");
+        tree.write("sys/class/net/enp5s0/speed", "1000\n");
+        tree.mkdir("sys/class/net/docker0");
+        tree.mkdir("sys/class/net/lo");
+
+        let mut source = NetDevSource::with_paths(&net_dev, tree.path("sys/class/net"));
+        source.init().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+        let names: Vec<&str> = m.interfaces.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["enp5s0"]);
+        assert_eq!(m.interfaces[0].link_speed_mbps, 1000);
+        assert_eq!(source.health(), SourceHealth::Ok);
+
+        // The NIC is unplugged: only virtual interfaces remain, which are
+        // then shown instead of nothing.
+        tree.write("proc/net/dev", &NET_DEV.replace("enp5s0", "usb0"));
+        std::fs::remove_dir_all(tree.path("sys/class/net/enp5s0")).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        source.sample(&mut m);
+        let names: Vec<&str> = m.interfaces.iter().map(|i| i.name.as_str()).collect();
+        // usb0 is new and has no baseline yet.
+        assert_eq!(names, ["docker0"]);
+        assert_eq!(m.net_rx_bps, 0);
+    }
+}
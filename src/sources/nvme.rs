@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::hwmon::read_chip;
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{HardwareMetrics, NvmeController, SensorKind, SmartLog};
+use crate::topology::SYS_CLASS_NVME;
+
+// The health log changes slowly and every read is an admin command to the
+// drive, so it is fetched far less often than the sample interval.
+const SMART_INTERVAL: Duration = Duration::from_secs(30);
+
+const SMART_LOG_LEN: usize = 512;
+
+// Per admin command. 0 would mean the kernel's admin_timeout, 60 s by
+// default, for a drive that stopped answering.
+const SMART_TIMEOUT_MS: u32 = 5000;
+
+// struct nvme_passthru_cmd from <linux/nvme_ioctl.h>.
+#[repr(C)]
+#[derive(Default)]
+struct PassthruCmd {
+    opcode: u8,
+    flags: u8,
+    rsvd1: u16,
+    nsid: u32,
+    cdw2: u32,
+    cdw3: u32,
+    metadata: u64,
+    addr: u64,
+    metadata_len: u32,
+    data_len: u32,
+    cdw10: u32,
+    cdw11: u32,
+    cdw12: u32,
+    cdw13: u32,
+    cdw14: u32,
+    cdw15: u32,
+    timeout_ms: u32,
+    result: u32,
+}
+
+const _: () = assert!(std::mem::size_of::<PassthruCmd>() == 72);
+
+// _IOWR('N', 0x41, struct nvme_passthru_cmd)
+const NVME_IOCTL_ADMIN_CMD: u64 = (3 << 30) | ((std::mem::size_of::<PassthruCmd>() as u64) << 16) | ((b'N' as u64) << 8) | 0x41;
+const GET_LOG_PAGE: u8 = 0x02;
+const LOG_SMART: u32 = 0x02;
+
+// Decodes the SMART / Health Information log page. Counters are 128-bit
+// little endian; data units are thousands of 512-byte blocks.
+pub fn parse_smart_log(page: &[u8]) -> Option<SmartLog> {
+    let bytes = page.get(..SMART_LOG_LEN)?;
+    // Nothing real comes near 2^64, so the upper half is ignored.
+    let counter = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+    let kelvin = u16::from_le_bytes([bytes[1], bytes[2]]);
+    Some(SmartLog {
+        critical_warning: bytes[0],
+        temperature_c: kelvin as f32 - 273.15,
+        available_spare_pct: bytes[3],
+        available_spare_threshold_pct: bytes[4],
+        percentage_used: bytes[5],
+        data_read_bytes: counter(32).saturating_mul(512_000),
+        data_written_bytes: counter(48).saturating_mul(512_000),
+        power_on_hours: counter(128),
+        unsafe_shutdowns: counter(144),
+        media_errors: counter(160),
+        error_log_entries: counter(176),
+    })
+}
+
+// Reads the controller-wide health log through the admin passthrough ioctl
+// on the character device, e.g. /dev/nvme0.
+fn read_smart_log(dev: &Path) -> Result<SmartLog, String> {
+    let permission = |e: std::io::Error| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => format!("{}: permission denied, SMART needs root", dev.display()),
+        _ => format!("{}: {}", dev.display(), e),
+    };
+    let file = File::open(dev).map_err(permission)?;
+    let mut page = [0u8; SMART_LOG_LEN];
+    let mut cmd = PassthruCmd {
+        opcode: GET_LOG_PAGE,
+        // All namespaces: the controller-wide log.
+        nsid: 0xffff_ffff,
+        addr: page.as_mut_ptr() as u64,
+        data_len: SMART_LOG_LEN as u32,
+        // Number of dwords minus one in the upper half, log id in the lower.
+        cdw10: ((SMART_LOG_LEN as u32 / 4 - 1) << 16) | LOG_SMART,
+        timeout_ms: SMART_TIMEOUT_MS,
+        ..Default::default()
+    };
+    // SAFETY: cmd matches the kernel's struct and addr points at a buffer of
+    // data_len bytes that outlives the call.
+    let status = unsafe { libc::ioctl(file.as_raw_fd(), NVME_IOCTL_ADMIN_CMD as _, &mut cmd) };
+    if status < 0 {
+        return Err(permission(std::io::Error::last_os_error()));
+    }
+    // Positive values are NVMe status codes from the controller.
+    if status > 0 {
+        return Err(format!("{}: controller returned status {:#x}", dev.display(), status));
+    }
+    parse_smart_log(&page).ok_or_else(|| format!("{}: short log page", dev.display()))
+}
+
+// The composite temperature and its critical threshold from the hwmon chip
+// the nvme driver registers below the controller.
+fn read_temperature(controller: &Path) -> (Option<f64>, Option<f64>) {
+    let chip = std::fs::read_dir(controller)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .find(|e| e.file_name().to_str().is_some_and(|n| n.starts_with("hwmon")))
+        .and_then(|e| read_chip(&e.path()));
+    let sensor = chip.and_then(|chip| chip.sensors.into_iter().find(|s| s.kind == SensorKind::Temperature));
+    (sensor.as_ref().map(|s| s.value), sensor.and_then(|s| s.crit))
+}
+
+struct Smart {
+    // When the last read was queued.
+    requested: Instant,
+    // None until the worker has answered once.
+    log: Option<Result<SmartLog, String>>,
+}
+
+// controller name -> its health log, shared with the workers.
+type SmartCache = Arc<Mutex<HashMap<String, Smart>>>;
+
+// Reads one controller's health log off the polling thread, so a controller
+// that stops answering holds up only its own log, for up to SMART_TIMEOUT_MS
+// per command, not the other controllers' or any source's sample. Exits
+// when the source drops its sender.
+fn spawn_smart_worker(name: &str, dev: PathBuf, cache: SmartCache) -> Result<Sender<()>, String> {
+    let (requests, queue) = mpsc::channel::<()>();
+    let controller = name.to_string();
+    std::thread::Builder::new()
+        .name(format!("nvme-smart-{}", name))
+        .spawn(move || {
+            while queue.recv().is_ok() {
+                // Requests that piled up behind a hung command get one read.
+                while queue.try_recv().is_ok() {}
+                let log = read_smart_log(&dev);
+                // Dropped if the controller went away in the meantime.
+                if let Some(smart) = cache.lock().unwrap().get_mut(&controller) {
+                    smart.log = Some(log);
+                }
+            }
+        })
+        .map_err(|e| format!("nvme-smart-{}: {}", name, e))?;
+    Ok(requests)
+}
+
+// Identity, state, temperature and the SMART health log of every NVMe
+// controller.
+pub struct NvmeSource {
+    sys_class_nvme: PathBuf,
+    dev: PathBuf,
+    smart: SmartCache,
+    // One SMART worker per controller, started on its first request.
+    workers: HashMap<String, Sender<()>>,
+    health: SourceHealth,
+}
+
+impl NvmeSource {
+    pub fn new() -> Self {
+        Self::with_paths(SYS_CLASS_NVME, "/dev")
+    }
+
+    // Lets the source run against a fake sysfs tree and device directory.
+    pub fn with_paths(sys_class_nvme: impl Into<PathBuf>, dev: impl Into<PathBuf>) -> Self {
+        Self {
+            sys_class_nvme: sys_class_nvme.into(),
+            dev: dev.into(),
+            smart: SmartCache::default(),
+            workers: HashMap::new(),
+            health: SourceHealth::Pending,
+        }
+    }
+
+    fn controller_names(&self) -> Result<Vec<String>, String> {
+        let entries = std::fs::read_dir(&self.sys_class_nvme).map_err(|e| format!("{}: {}", self.sys_class_nvme.display(), e))?;
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            // Controllers only; "nvme-subsys0" lives in its own class.
+            .filter(|name| name.strip_prefix("nvme").is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())))
+            .collect();
+        names.sort_by_key(|name| name[4..].parse::<u32>().unwrap_or(u32::MAX));
+        Ok(names)
+    }
+
+    fn read_controller(&mut self, name: &str, now: Instant) -> NvmeController {
+        let dir = self.sys_class_nvme.join(name);
+        let read = |file: &str| std::fs::read_to_string(dir.join(file)).map(|text| text.trim().to_string()).unwrap_or_default();
+        let (temperature_c, temperature_crit_c) = read_temperature(&dir);
+        let state = read("state");
+        let mut cache = self.smart.lock().unwrap();
+        let stale = cache.get(name).is_none_or(|smart| now.duration_since(smart.requested) >= SMART_INTERVAL);
+        // Admin commands to a controller that isn't live just time out.
+        if stale && state == "live" {
+            // The previous log stays up until the new one is in.
+            let smart = cache.entry(name.to_string()).or_insert(Smart { requested: now, log: None });
+            smart.requested = now;
+            if !self.workers.contains_key(name) {
+                match spawn_smart_worker(name, self.dev.join(name), Arc::clone(&self.smart)) {
+                    Ok(worker) => {
+                        self.workers.insert(name.to_string(), worker);
+                    }
+                    Err(e) => smart.log = Some(Err(e)),
+                }
+            }
+            if let Some(worker) = self.workers.get(name) {
+                let _ = worker.send(());
+            }
+        }
+        let smart = match cache.get(name) {
+            Some(Smart { log: Some(log), .. }) => log.clone(),
+            Some(Smart { log: None, .. }) => Err("SMART log not read yet".to_string()),
+            None => Err(format!("controller is {}", state)),
+        };
+        NvmeController {
+            name: name.to_string(),
+            model: read("model"),
+            serial: read("serial"),
+            firmware: read("firmware_rev"),
+            transport: read("transport"),
+            address: read("address"),
+            state,
+            temperature_c,
+            temperature_crit_c,
+            smart,
+        }
+    }
+}
+
+impl MetricSource for NvmeSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "nvme", description: "NVMe health (/sys/class/nvme)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        let result = match self.controller_names() {
+            Ok(names) if names.is_empty() => Err(format!("{}: no controllers", self.sys_class_nvme.display())),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        };
+        self.health = match &result {
+            Ok(()) => SourceHealth::Ok,
+            Err(e) => SourceHealth::Unavailable(e.clone()),
+        };
+        result
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let names = match self.controller_names() {
+            Ok(names) => names,
+            Err(e) => {
+                self.health = SourceHealth::Degraded(e);
+                return;
+            }
+        };
+        let now = Instant::now();
+        // Drop cached logs and workers of controllers that went away.
+        self.smart.lock().unwrap().retain(|name, _| names.contains(name));
+        self.workers.retain(|name, _| names.contains(name));
+        m.nvme = names.iter().map(|name| self.read_controller(name, now)).collect();
+        // An unreadable SMART log is reported per controller, not as a
+        // source failure: without root it's the normal case.
+        self.health = SourceHealth::Ok;
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    // A health log page as a worn Samsung drive returns it, reduced to the
+    // fields that are decoded.
+    fn page() -> [u8; SMART_LOG_LEN] {
+        let mut page = [0u8; SMART_LOG_LEN];
+        // Spare below threshold and reliability degraded.
+        page[0] = 0b0000_0101;
+        page[1..3].copy_from_slice(&318u16.to_le_bytes());
+        page[3] = 8;
+        page[4] = 10;
+        page[5] = 104;
+        let mut counter = |offset: usize, value: u64| page[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        counter(32, 1_000);
+        counter(48, 2_000_000);
+        counter(128, 12_345);
+        counter(144, 17);
+        counter(160, 2);
+        counter(176, 9);
+        page
+    }
+
+    #[test]
+    fn decodes_the_health_log() {
+        let log = parse_smart_log(&page()).unwrap();
+        assert!((log.temperature_c - 44.85).abs() < 1e-3);
+        assert_eq!((log.available_spare_pct, log.available_spare_threshold_pct, log.percentage_used), (8, 10, 104));
+        // Data units are thousands of 512-byte blocks.
+        assert_eq!((log.data_read_bytes, log.data_written_bytes), (512_000_000, 1_024_000_000_000));
+        assert_eq!((log.power_on_hours, log.unsafe_shutdowns, log.media_errors, log.error_log_entries), (12_345, 17, 2, 9));
+        assert_eq!(log.warnings(), ["spare below threshold", "reliability degraded"]);
+        assert!(log.spare_low());
+    }
+
+    #[test]
+    fn short_page_is_rejected() {
+        assert!(parse_smart_log(&page()[..SMART_LOG_LEN - 1]).is_none());
+    }
+
+    fn controller(tree: &TempTree, name: &str, state: &str) {
+        for (file, value) in [("model", "Samsung SSD 980 PRO 1TB"), ("serial", "S5GXNX0T123456"), ("firmware_rev", "5B2QGXA7"), ("transport", "pcie"), ("address", "0000:03:00.0"), ("state", state)] {
+            // sysfs pads model and serial with spaces.
+            tree.write(&format!("class/nvme/{}/{}", name, file), &format!("{}   \n", value));
+        }
+    }
+
+    #[test]
+    fn reads_controllers_from_sysfs() {
+        let tree = TempTree::new();
+        controller(&tree, "nvme0", "live");
+        tree.write("class/nvme/nvme0/hwmon3/name", "nvme\n");
+        tree.write("class/nvme/nvme0/hwmon3/temp1_input", "41850\n");
+        tree.write("class/nvme/nvme0/hwmon3/temp1_crit", "84850\n");
+        controller(&tree, "nvme10", "live");
+        controller(&tree, "nvme1", "resetting");
+        tree.mkdir("class/nvme/nvme-subsys0");
+        // Not a character device, so the admin command fails with ENOTTY.
+        tree.write("dev/nvme0", "");
+
+        let mut source = NvmeSource::with_paths(tree.path("class/nvme"), tree.path("dev"));
+        source.init().unwrap();
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+        let names: Vec<&str> = m.nvme.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["nvme0", "nvme1", "nvme10"]);
+        let nvme0 = &m.nvme[0];
+        assert_eq!((nvme0.model.as_str(), nvme0.serial.as_str(), nvme0.state.as_str()), ("Samsung SSD 980 PRO 1TB", "S5GXNX0T123456", "live"));
+        assert_eq!((nvme0.temperature_c, nvme0.temperature_crit_c), (Some(41.85), Some(84.85)));
+        assert_eq!(m.nvme[1].smart.as_ref().err().map(String::as_str), Some("controller is resetting"));
+        assert_eq!(source.health(), SourceHealth::Ok);
+
+        // The log comes from the worker; sampling never waits for it.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while m.nvme[0].smart.as_ref().is_err_and(|e| e == "SMART log not read yet") && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            source.sample(&mut m);
+        }
+        let error = m.nvme[0].smart.as_ref().err().unwrap();
+        assert!(error.starts_with(&format!("{}: ", tree.path("dev/nvme0").display())), "{}", error);
+        // nvme10 has no device node at all.
+        assert!(m.nvme[2].smart.is_err());
+    }
+
+    #[test]
+    fn hung_controller_holds_up_only_its_own_log() {
+        let tree = TempTree::new();
+        controller(&tree, "nvme0", "live");
+        controller(&tree, "nvme1", "live");
+        // Opening a FIFO blocks until someone opens the other end: a
+        // controller that never answers.
+        let fifo = tree.mkdir("dev").join("nvme0");
+        let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        // SAFETY: c_path is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+        tree.write("dev/nvme1", "");
+
+        let mut source = NvmeSource::with_paths(tree.path("class/nvme"), tree.path("dev"));
+        source.init().unwrap();
+        let mut m = HardwareMetrics::default();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            source.sample(&mut m);
+            if m.nvme[1].smart.as_ref().is_err_and(|e| e != "SMART log not read yet") || Instant::now() > deadline {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(m.nvme[1].smart.as_ref().err().unwrap().starts_with(&format!("{}: ", tree.path("dev/nvme1").display())));
+        assert_eq!(m.nvme[0].smart.as_ref().err().map(String::as_str), Some("SMART log not read yet"));
+
+        // Let nvme0's worker finish.
+        drop(File::options().write(true).open(&fifo).unwrap());
+    }
+}
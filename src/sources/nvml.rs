@@ -0,0 +1,133 @@
+use nvml_wrapper::bitmasks::device::ThrottleReasons;
+use nvml_wrapper::enum_wrappers::device::{Clock, PcieUtilCounter, TemperatureSensor};
+use nvml_wrapper::{Device, Nvml};
+use std::path::PathBuf;
+
+use super::{GpuBackend, GpuIdentity};
+use crate::metrics::{PcieLink, ThrottleReason};
+use crate::pcie;
+
+const THROTTLE_REASONS: [(ThrottleReasons, ThrottleReason); 9] = [
+    (ThrottleReasons::GPU_IDLE, ThrottleReason::Idle),
+    (ThrottleReasons::APPLICATIONS_CLOCKS_SETTING, ThrottleReason::ApplicationClocks),
+    (ThrottleReasons::SW_POWER_CAP, ThrottleReason::SwPowerCap),
+    (ThrottleReasons::HW_SLOWDOWN, ThrottleReason::HwSlowdown),
+    (ThrottleReasons::SYNC_BOOST, ThrottleReason::SyncBoost),
+    (ThrottleReasons::SW_THERMAL_SLOWDOWN, ThrottleReason::SwThermal),
+    (ThrottleReasons::HW_THERMAL_SLOWDOWN, ThrottleReason::HwThermal),
+    (ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN, ThrottleReason::HwPowerBrake),
+    (ThrottleReasons::DISPLAY_CLOCK_SETTING, ThrottleReason::DisplayClocks),
+];
+
+// NVIDIA GPU metrics via NVML.
+// Note: We track PCIe utilization here because NVML provides high-fidelity access
+// to NVIDIA-specific bus metrics. Standard Windows PDH counters usually don't
+// expose generic PCIe bus utilization for all devices in a unified way.
+pub struct NvmlBackend {
+    nvml: Option<Nvml>,
+    // Where to look up the link when NVML can't report it.
+    sys_pci_devices: PathBuf,
+}
+
+impl NvmlBackend {
+    pub fn new() -> Self {
+        Self { nvml: None, sys_pci_devices: PathBuf::from(pcie::SYS_PCI_DEVICES) }
+    }
+
+    fn device(&self, index: u32) -> Result<Device<'_>, String> {
+        let nvml = self.nvml.as_ref().ok_or_else(|| "NVML not initialised".to_string())?;
+        nvml.device_by_index(index).map_err(|e| e.to_string())
+    }
+}
+
+impl GpuBackend for NvmlBackend {
+    fn description(&self) -> &'static str {
+        "NVIDIA GPU (NVML)"
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        self.nvml = Some(Nvml::init().map_err(|e| e.to_string())?);
+        Ok(())
+    }
+
+    fn device_count(&self) -> Result<u32, String> {
+        let nvml = self.nvml.as_ref().ok_or_else(|| "NVML not initialised".to_string())?;
+        nvml.device_count().map_err(|e| e.to_string())
+    }
+
+    fn identity(&self, index: u32) -> Result<GpuIdentity, String> {
+        let device = self.device(index)?;
+        Ok(GpuIdentity {
+            name: device.name().unwrap_or_else(|_| "Unknown GPU".to_string()),
+            uuid: device.uuid().unwrap_or_default(),
+            pci_bus_id: device.pci_info().map(|pci| pci.bus_id).unwrap_or_default(),
+        })
+    }
+
+    fn memory(&self, index: u32) -> Result<(u64, u64), String> {
+        let mem = self.device(index)?.memory_info().map_err(|e| e.to_string())?;
+        Ok((mem.used, mem.total))
+    }
+
+    fn pcie_throughput(&self, index: u32) -> Result<(u64, u64), String> {
+        let device = self.device(index)?;
+        // Reported in KB/s.
+        let tx = device.pcie_throughput(PcieUtilCounter::Send).map_err(|e| e.to_string())?;
+        let rx = device.pcie_throughput(PcieUtilCounter::Receive).map_err(|e| e.to_string())?;
+        Ok((tx as u64 * 1024, rx as u64 * 1024))
+    }
+
+    fn utilization(&self, index: u32) -> Result<(u32, u32), String> {
+        let util = self.device(index)?.utilization_rates().map_err(|e| e.to_string())?;
+        Ok((util.gpu, util.memory))
+    }
+
+    fn clocks(&self, index: u32) -> Result<(u32, u32), String> {
+        let device = self.device(index)?;
+        let graphics = device.clock_info(Clock::Graphics).map_err(|e| e.to_string())?;
+        let memory = device.clock_info(Clock::Memory).map_err(|e| e.to_string())?;
+        Ok((graphics, memory))
+    }
+
+    fn max_clocks(&self, index: u32) -> Result<(u32, u32), String> {
+        let device = self.device(index)?;
+        let graphics = device.max_clock_info(Clock::Graphics).map_err(|e| e.to_string())?;
+        let memory = device.max_clock_info(Clock::Memory).map_err(|e| e.to_string())?;
+        Ok((graphics, memory))
+    }
+
+    fn temperature(&self, index: u32) -> Result<u32, String> {
+        self.device(index)?.temperature(TemperatureSensor::Gpu).map_err(|e| e.to_string())
+    }
+
+    fn power(&self, index: u32) -> Result<(f32, f32), String> {
+        let device = self.device(index)?;
+        // Both in milliwatts.
+        let draw = device.power_usage().map_err(|e| e.to_string())?;
+        let limit = device.enforced_power_limit().map_err(|e| e.to_string())?;
+        Ok((draw as f32 / 1000.0, limit as f32 / 1000.0))
+    }
+
+    fn pcie_link(&self, index: u32) -> Result<PcieLink, String> {
+        let device = self.device(index)?;
+        let link = || -> Result<PcieLink, String> {
+            Ok(PcieLink {
+                gen: device.current_pcie_link_gen().map_err(|e| e.to_string())?,
+                width: device.current_pcie_link_width().map_err(|e| e.to_string())?,
+                max_gen: device.max_pcie_link_gen().map_err(|e| e.to_string())?,
+                max_width: device.max_pcie_link_width().map_err(|e| e.to_string())?,
+            })
+        };
+        // Older drivers and some virtualised GPUs don't report the link; on
+        // Linux the kernel knows it from the bus address.
+        link().or_else(|e| {
+            let bus_id = device.pci_info().map_err(|_| e.clone())?.bus_id;
+            pcie::sysfs_link(&self.sys_pci_devices, &bus_id).ok_or(e)
+        })
+    }
+
+    fn throttle_reasons(&self, index: u32) -> Result<Vec<ThrottleReason>, String> {
+        let reasons = self.device(index)?.current_throttle_reasons().map_err(|e| e.to_string())?;
+        Ok(THROTTLE_REASONS.iter().filter(|(flag, _)| reasons.contains(*flag)).map(|&(_, reason)| reason).collect())
+    }
+}
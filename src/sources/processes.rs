@@ -0,0 +1,79 @@
+use std::time::Instant;
+
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind, Users};
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{HardwareMetrics, ProcessMetrics};
+
+fn refresh_kind() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing().with_cpu().with_memory().with_disk_usage().with_user(UpdateKind::OnlyIfNotSet)
+}
+
+// Per-process CPU, memory and I/O via sysinfo, for platforms without procfs.
+// sysinfo's disk usage counts all of a process's I/O on Windows, network and
+// pipes included, so it overstates storage traffic there.
+pub struct ProcessesSource {
+    sys: Option<System>,
+    users: Option<Users>,
+    previous_at: Option<Instant>,
+}
+
+impl ProcessesSource {
+    pub fn new() -> Self {
+        Self { sys: None, users: None, previous_at: None }
+    }
+}
+
+impl MetricSource for ProcessesSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "procs", description: "Processes (sysinfo)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        let mut sys = System::new();
+        // CPU usage and I/O are deltas against the previous refresh.
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind());
+        self.sys = Some(sys);
+        self.users = Some(Users::new_with_refreshed_list());
+        self.previous_at = Some(Instant::now());
+        Ok(())
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let (Some(sys), Some(users)) = (self.sys.as_mut(), self.users.as_ref()) else { return };
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind());
+        let now = Instant::now();
+        let elapsed = self.previous_at.map(|t| now.duration_since(t).as_secs_f64()).unwrap_or(0.0);
+        self.previous_at = Some(now);
+        if elapsed <= 0.0 {
+            return;
+        }
+
+        let rate = |bytes: u64| (bytes as f64 / elapsed) as u64;
+        m.processes = sys
+            .processes()
+            .iter()
+            .map(|(pid, process)| {
+                let io = process.disk_usage();
+                ProcessMetrics {
+                    pid: pid.as_u32(),
+                    name: process.name().to_string_lossy().into_owned(),
+                    user: process
+                        .user_id()
+                        .and_then(|uid| users.get_user_by_id(uid))
+                        .map(|user| user.name().to_string())
+                        .unwrap_or_default(),
+                    cpu_pct: process.cpu_usage(),
+                    rss_bytes: process.memory(),
+                    read_bps: Some(rate(io.read_bytes)),
+                    write_bps: Some(rate(io.written_bytes)),
+                    cgroup: String::new(),
+                }
+            })
+            .collect();
+    }
+
+    fn health(&self) -> SourceHealth {
+        if self.sys.is_some() { SourceHealth::Ok } else { SourceHealth::Pending }
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{HardwareMetrics, ProcessMetrics};
+
+// Clock ticks per second of the utime/stime fields (USER_HZ). 100 on most
+// architectures whatever the kernel's internal HZ, but 1024 on alpha.
+fn user_hz() -> f64 {
+    // SAFETY: sysconf only reads a configuration value.
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as f64,
+        _ => 100.0,
+    }
+}
+
+// The fields of /proc/<pid>/stat we use.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PidStat {
+    pub comm: String,
+    // utime + stime, in clock ticks.
+    pub cpu_ticks: u64,
+    // Clock ticks after boot the process started; tells a reused pid apart.
+    pub start_time: u64,
+}
+
+// The name is in parentheses and may itself contain spaces and parentheses,
+// so the other fields are counted from the last ')'.
+pub fn parse_pid_stat(text: &str) -> Option<PidStat> {
+    let open = text.find('(')?;
+    let close = text.rfind(')')?;
+    let fields: Vec<&str> = text.get(close + 1..)?.split_whitespace().collect();
+    // Numbered as in proc(5), where the state right after the name is field 3.
+    let field = |n: usize| fields.get(n - 3)?.parse::<u64>().ok();
+    Some(PidStat {
+        comm: text.get(open + 1..close)?.to_string(),
+        cpu_ticks: field(14)? + field(15)?,
+        start_time: field(22)?,
+    })
+}
+
+// (real uid, resident set in bytes) from /proc/<pid>/status. Kernel threads
+// have no VmRSS line and count as 0.
+pub fn parse_pid_status(text: &str) -> Option<(u32, u64)> {
+    let mut uid = None;
+    let mut rss_bytes = 0;
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("Uid:") {
+            uid = rest.split_whitespace().next().and_then(|u| u.parse().ok());
+        } else if let Some(rest) = line.strip_prefix("VmRSS:") {
+            rss_bytes = rest.split_whitespace().next().and_then(|kb| kb.parse::<u64>().ok()).unwrap_or(0) * 1024;
+        }
+    }
+    Some((uid?, rss_bytes))
+}
+
+// (read_bytes, write_bytes) from /proc/<pid>/io: what went to the block
+// layer, as opposed to rchar/wchar, which include page cache hits.
+pub fn parse_pid_io(text: &str) -> Option<(u64, u64)> {
+    let value = |key: &str| text.lines().find_map(|line| line.strip_prefix(key)?.trim().parse::<u64>().ok());
+    Some((value("read_bytes:")?, value("write_bytes:")?))
+}
+
+// The cgroup v2 path from /proc/<pid>/cgroup, the "0::" line. Pure v1
+// hosts don't have one.
+pub fn parse_pid_cgroup(text: &str) -> Option<String> {
+    text.lines().find_map(|line| line.strip_prefix("0::")).map(str::to_string)
+}
+
+// uid -> login name from /etc/passwd.
+pub fn parse_passwd(text: &str) -> HashMap<u32, String> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+struct PidSample {
+    stat: PidStat,
+    uid: u32,
+    rss_bytes: u64,
+    io: Option<(u64, u64)>,
+    cgroup: String,
+}
+
+// Per-process CPU, memory and disk I/O on Linux from /proc/<pid>.
+pub struct ProcPidSource {
+    proc: PathBuf,
+    passwd: PathBuf,
+    users: HashMap<u32, String>,
+    previous: HashMap<u32, PidSample>,
+    previous_at: Option<Instant>,
+    // USER_HZ, read once.
+    user_hz: f64,
+    health: SourceHealth,
+}
+
+impl ProcPidSource {
+    pub fn new() -> Self {
+        Self::with_paths("/proc", "/etc/passwd")
+    }
+
+    // Lets the source run against a fake procfs tree.
+    pub fn with_paths(proc: impl Into<PathBuf>, passwd: impl Into<PathBuf>) -> Self {
+        Self {
+            proc: proc.into(),
+            passwd: passwd.into(),
+            users: HashMap::new(),
+            previous: HashMap::new(),
+            previous_at: None,
+            user_hz: user_hz(),
+            health: SourceHealth::Pending,
+        }
+    }
+
+    fn read_pid(dir: &Path) -> Option<PidSample> {
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+        let stat = parse_pid_stat(&read("stat")?)?;
+        let (uid, rss_bytes) = parse_pid_status(&read("status")?)?;
+        // Only readable for our own processes unless we run as root.
+        let io = read("io").and_then(|text| parse_pid_io(&text));
+        let cgroup = read("cgroup").and_then(|text| parse_pid_cgroup(&text)).unwrap_or_default();
+        Some(PidSample { stat, uid, rss_bytes, io, cgroup })
+    }
+
+    // Processes that exit between the listing and the read are skipped.
+    fn read(&self) -> Result<HashMap<u32, PidSample>, String> {
+        let entries = std::fs::read_dir(&self.proc).map_err(|e| format!("{}: {}", self.proc.display(), e))?;
+        Ok(entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                Some((pid, Self::read_pid(&entry.path())?))
+            })
+            .collect())
+    }
+
+    fn user_name(&self, uid: u32) -> String {
+        self.users.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+    }
+}
+
+impl MetricSource for ProcPidSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "procs", description: "Processes (/proc/<pid>)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        // Users from LDAP and the like aren't in the file; they show as uids.
+        self.users = std::fs::read_to_string(&self.passwd).map(|text| parse_passwd(&text)).unwrap_or_default();
+        match self.read() {
+            Ok(samples) => {
+                self.previous = samples;
+                self.previous_at = Some(Instant::now());
+                self.health = SourceHealth::Ok;
+                Ok(())
+            }
+            Err(e) => {
+                self.health = SourceHealth::Unavailable(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let samples = match self.read() {
+            Ok(samples) => samples,
+            Err(e) => {
+                self.health = SourceHealth::Degraded(e);
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        let elapsed = self.previous_at.map(|t| now.duration_since(t).as_secs_f64()).unwrap_or(0.0);
+        if elapsed > 0.0 {
+            let rate = |now: u64, before: u64| (now.saturating_sub(before) as f64 / elapsed) as u64;
+            m.processes = samples
+                .iter()
+                .filter_map(|(&pid, cur)| {
+                    let prev = self.previous.get(&pid).filter(|p| p.stat.start_time == cur.stat.start_time)?;
+                    let io = cur.io.zip(prev.io);
+                    Some(ProcessMetrics {
+                        pid,
+                        name: cur.stat.comm.clone(),
+                        user: self.user_name(cur.uid),
+                        cpu_pct: (cur.stat.cpu_ticks.saturating_sub(prev.stat.cpu_ticks) as f64 / self.user_hz / elapsed
+                            * 100.0) as f32,
+                        rss_bytes: cur.rss_bytes,
+                        read_bps: io.map(|((read, _), (before, _))| rate(read, before)),
+                        write_bps: io.map(|((_, write), (_, before))| rate(write, before)),
+                        cgroup: cur.cgroup.clone(),
+                    })
+                })
+                .collect();
+        }
+        self.previous = samples;
+        self.previous_at = Some(now);
+        self.health = SourceHealth::Ok;
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    fn stat_line(pid: u32, comm: &str, utime: u64, stime: u64, start_time: u64) -> String {
+        format!(
+            "{} ({}) S 1 {} {} 0 -1 4194560 8311 0 0 0 {} {} 0 0 20 0 4 0 {} 1226752000 51234 18446744073709551615\n",
+            pid, comm, pid, pid, utime, stime, start_time
+        )
+    }
+
+    #[test]
+    fn stat_comm_with_spaces_and_parentheses() {
+        let stat = parse_pid_stat(&stat_line(4242, "tmux: server (1)", 700, 300, 91234)).unwrap();
+        assert_eq!(stat, PidStat { comm: "tmux: server (1)".to_string(), cpu_ticks: 1000, start_time: 91234 });
+        // A ')' followed by something that looks like fields doesn't fool it.
+        let stat = parse_pid_stat(&stat_line(7, ") S 1 2 3", 1, 2, 3)).unwrap();
+        assert_eq!((stat.comm.as_str(), stat.cpu_ticks, stat.start_time), (") S 1 2 3", 3, 3));
+        assert!(parse_pid_stat("7 (truncated) S 1 2").is_none());
+    }
+
+    #[test]
+    fn status_of_a_kernel_thread_has_no_rss() {
+        let kthread = "Name:\tkworker/3:1-events\nUmask:\t0000\nState:\tI (idle)\nTgid:\t812\nPid:\t812\nPPid:\t2\nUid:\t0\t0\t0\t0\nGid:\t0\t0\t0\t0\nThreads:\t1\n";
+        assert_eq!(parse_pid_status(kthread), Some((0, 0)));
+        let user = "Name:\tpython3\nUid:\t1000\t1000\t1000\t1000\nVmPeak:\t 9001 kB\nVmRSS:\t  204800 kB\n";
+        assert_eq!(parse_pid_status(user), Some((1000, 204800 * 1024)));
+        assert_eq!(parse_pid_status("Name:\tgone\n"), None);
+    }
+
+    #[test]
+    fn io_is_block_layer_bytes() {
+        let io = "rchar: 98765432\nwchar: 1234567\nsyscr: 1000\nsyscw: 200\nread_bytes: 40960\nwrite_bytes: 8192\ncancelled_write_bytes: 4096\n";
+        assert_eq!(parse_pid_io(io), Some((40960, 8192)));
+        assert_eq!(parse_pid_io("rchar: 1\nwchar: 2\n"), None);
+    }
+
+    #[test]
+    fn cgroup_and_passwd() {
+        assert_eq!(parse_pid_cgroup("1:name=systemd:/\n0::/system.slice/sshd.service\n").as_deref(), Some("/system.slice/sshd.service"));
+        assert_eq!(parse_pid_cgroup("1:cpu,cpuacct:/\n"), None);
+        let users = parse_passwd("root:x:0:0:root:/root:/bin/bash\nbroken\nalice:x:1000:1000::/home/alice:/bin/sh\n");
+        assert_eq!((users[&0].as_str(), users[&1000].as_str(), users.len()), ("root", "alice", 2));
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn user_hz_comes_from_sysconf() {
+        assert_eq!(user_hz(), 100.0);
+    }
+
+    #[test]
+    fn source_skips_new_and_reused_pids() {
+        let tree = TempTree::new();
+        let process = |pid: u32, utime: u64, start_time: u64| {
+            tree.write(&format!("proc/{}/stat", pid), &stat_line(pid, "worker", utime, 0, start_time));
+            tree.write(&format!("proc/{}/status", pid), "Uid:\t1000\t1000\t1000\t1000\nVmRSS:\t1024 kB\n");
+            tree.write(&format!("proc/{}/cgroup", pid), "0::/user.slice\n");
+        };
+        process(100, 0, 500);
+        process(200, 0, 600);
+        let passwd = tree.write("passwd", "alice:x:1000:1000::/home/alice:/bin/sh\n");
+
+        let mut source = ProcPidSource::with_paths(tree.path("proc"), &passwd);
+        source.init().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        process(100, 1, 500);
+        // pid 200 exited and the pid was reused; 300 is new.
+        process(200, 0, 900);
+        process(300, 0, 950);
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+
+        assert_eq!(m.processes.iter().map(|p| p.pid).collect::<Vec<_>>(), [100]);
+        let p = &m.processes[0];
+        assert_eq!((p.user.as_str(), p.rss_bytes, p.cgroup.as_str()), ("alice", 1024 * 1024, "/user.slice"));
+        assert!(p.cpu_pct > 0.0);
+        // No io file: not ours to read.
+        assert_eq!(p.read_bps, None);
+    }
+}
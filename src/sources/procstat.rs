@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{CoreMetrics, CpuTimes, HardwareMetrics, MemoryDetail};
+
+const GIB: f32 = 1024.0 * 1024.0 * 1024.0;
+
+// Cumulative jiffies from one `cpu` line of /proc/stat. guest and guest_nice
+// are already included in user and nice, so they are not kept separately.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpuStat {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
+
+impl CpuStat {
+    fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+}
+
+// The `cpu` lines of /proc/stat: the aggregate over all CPUs and one per
+// online logical CPU.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProcStat {
+    pub total: CpuStat,
+    pub cpus: Vec<(u32, CpuStat)>,
+}
+
+// Parses /proc/stat. Returns None without an aggregate `cpu` line. Kernels
+// older than 2.6.11 lack steal; missing trailing fields read as zero.
+pub fn parse_proc_stat(text: &str) -> Option<ProcStat> {
+    let mut total = None;
+    let mut cpus = Vec::new();
+    for line in text.lines().filter(|line| line.starts_with("cpu")) {
+        let mut fields = line.split_whitespace();
+        let label = fields.next().unwrap_or("");
+        let mut values = fields.map(|f| f.parse::<u64>().ok());
+        let mut next = || values.next().flatten().unwrap_or(0);
+        let stat = CpuStat {
+            user: next(),
+            nice: next(),
+            system: next(),
+            idle: next(),
+            iowait: next(),
+            irq: next(),
+            softirq: next(),
+            steal: next(),
+        };
+        match label.strip_prefix("cpu") {
+            Some("") => total = Some(stat),
+            Some(n) => {
+                if let Ok(n) = n.parse() {
+                    cpus.push((n, stat));
+                }
+            }
+            None => {}
+        }
+    }
+    Some(ProcStat { total: total?, cpus })
+}
+
+// Splits the time between two readings into percentages of the total, the
+// way top and mpstat do.
+pub fn cpu_times(prev: &CpuStat, cur: &CpuStat) -> CpuTimes {
+    let total = cur.total().saturating_sub(prev.total()) as f32;
+    let pct = |now: u64, before: u64| if total > 0.0 { now.saturating_sub(before) as f32 / total * 100.0 } else { 0.0 };
+    CpuTimes {
+        user: pct(cur.user, prev.user),
+        nice: pct(cur.nice, prev.nice),
+        system: pct(cur.system, prev.system),
+        iowait: pct(cur.iowait, prev.iowait),
+        irq: pct(cur.irq, prev.irq),
+        softirq: pct(cur.softirq, prev.softirq),
+        steal: pct(cur.steal, prev.steal),
+    }
+}
+
+// Parses /proc/meminfo into bytes, keyed by field name (`MemTotal`, ...).
+pub fn parse_meminfo(text: &str) -> HashMap<String, u64> {
+    text.lines()
+        .filter_map(|line| {
+            let (key, rest) = line.split_once(':')?;
+            let mut parts = rest.split_whitespace();
+            let value = parts.next()?.parse::<u64>().ok()?;
+            // Everything but the HugePages_* counts is in kB.
+            let bytes = if parts.next() == Some("kB") { value * 1024 } else { value };
+            Some((key.to_string(), bytes))
+        })
+        .collect()
+}
+
+// Static placement of one logical CPU, from /sys/devices/system/cpu/cpuN/topology.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CpuTopology {
+    pub core_id: u32,
+    pub package_id: u32,
+    pub max_freq_mhz: u32,
+}
+
+fn read_u32(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// Falls back to one core per CPU in package 0 when the kernel exposes no
+// topology (some VMs and containers).
+pub fn read_topology(sys_cpu: &Path, cpu: u32) -> CpuTopology {
+    let dir = sys_cpu.join(format!("cpu{}", cpu));
+    CpuTopology {
+        core_id: read_u32(&dir.join("topology/core_id")).unwrap_or(cpu),
+        package_id: read_u32(&dir.join("topology/physical_package_id")).unwrap_or(0),
+        max_freq_mhz: read_u32(&dir.join("cpufreq/cpuinfo_max_freq")).map_or(0, |khz| khz / 1000),
+    }
+}
+
+// Current clock of one logical CPU in MHz, 0 without cpufreq.
+pub fn read_freq_mhz(sys_cpu: &Path, cpu: u32) -> u32 {
+    read_u32(&sys_cpu.join(format!("cpu{}/cpufreq/scaling_cur_freq", cpu))).map_or(0, |khz| khz / 1000)
+}
+
+// Builds the memory breakdown. MemAvailable only exists since 3.14; before
+// that free + buffers + cache is the usual estimate.
+pub fn memory_detail(info: &HashMap<String, u64>) -> Option<MemoryDetail> {
+    let get = |key: &str| info.get(key).copied().unwrap_or(0);
+    let total = *info.get("MemTotal")?;
+    let available = info.get("MemAvailable").copied().unwrap_or(get("MemFree") + get("Buffers") + get("Cached"));
+    Some(MemoryDetail {
+        total_bytes: total,
+        available_bytes: available,
+        cached_bytes: get("Cached"),
+        buffers_bytes: get("Buffers"),
+        dirty_bytes: get("Dirty"),
+        swap_total_bytes: get("SwapTotal"),
+        swap_used_bytes: get("SwapTotal").saturating_sub(get("SwapFree")),
+    })
+}
+
+// CPU & RAM on Linux straight from procfs. Cheaper than sysinfo's
+// refresh, which also walks every process, and gives the per-mode splits.
+pub struct ProcStatSource {
+    stat: PathBuf,
+    meminfo: PathBuf,
+    sys_cpu: PathBuf,
+    previous: Option<ProcStat>,
+    // Looked up the first time a CPU shows up; CPUs can come online later.
+    topology: HashMap<u32, CpuTopology>,
+    health: SourceHealth,
+}
+
+impl ProcStatSource {
+    pub fn new() -> Self {
+        Self::with_paths("/proc/stat", "/proc/meminfo", "/sys/devices/system/cpu")
+    }
+
+    // Lets the source run against fixture files instead of the live procfs/sysfs.
+    pub fn with_paths(stat: impl Into<PathBuf>, meminfo: impl Into<PathBuf>, sys_cpu: impl Into<PathBuf>) -> Self {
+        Self {
+            stat: stat.into(),
+            meminfo: meminfo.into(),
+            sys_cpu: sys_cpu.into(),
+            previous: None,
+            topology: HashMap::new(),
+            health: SourceHealth::Pending,
+        }
+    }
+
+    fn read_stat(&self) -> Result<ProcStat, String> {
+        let text = std::fs::read_to_string(&self.stat).map_err(|e| format!("{}: {}", self.stat.display(), e))?;
+        parse_proc_stat(&text).ok_or_else(|| format!("{}: no aggregate cpu line", self.stat.display()))
+    }
+
+    fn read_meminfo(&self) -> Result<MemoryDetail, String> {
+        let text = std::fs::read_to_string(&self.meminfo).map_err(|e| format!("{}: {}", self.meminfo.display(), e))?;
+        memory_detail(&parse_meminfo(&text)).ok_or_else(|| format!("{}: no MemTotal", self.meminfo.display()))
+    }
+}
+
+impl MetricSource for ProcStatSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "cpu", description: "CPU & RAM (/proc/stat, /proc/meminfo)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        match self.read_stat().and_then(|stat| self.read_meminfo().map(|_| stat)) {
+            Ok(stat) => {
+                self.previous = Some(stat);
+                self.health = SourceHealth::Ok;
+                Ok(())
+            }
+            Err(e) => {
+                self.health = SourceHealth::Unavailable(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let (stat, memory) = match (self.read_stat(), self.read_meminfo()) {
+            (Ok(stat), Ok(memory)) => (stat, memory),
+            (Err(e), _) | (_, Err(e)) => {
+                self.health = SourceHealth::Degraded(e);
+                return;
+            }
+        };
+
+        if let Some(prev) = &self.previous {
+            let times = cpu_times(&prev.total, &stat.total);
+            // iowait is idle time as far as the CPU is concerned, same as sysinfo counts it.
+            m.cpu_usage = times.busy();
+            m.cpu_times = Some(times);
+
+            m.cores = stat
+                .cpus
+                .iter()
+                .map(|&(cpu, cur)| {
+                    let usage = prev.cpus.iter().find(|(n, _)| *n == cpu).map_or(0.0, |(_, p)| cpu_times(p, &cur).busy());
+                    let topo = *self.topology.entry(cpu).or_insert_with(|| read_topology(&self.sys_cpu, cpu));
+                    CoreMetrics {
+                        cpu,
+                        core_id: topo.core_id,
+                        package_id: topo.package_id,
+                        usage,
+                        freq_mhz: read_freq_mhz(&self.sys_cpu, cpu),
+                        max_freq_mhz: topo.max_freq_mhz,
+                    }
+                })
+                .collect();
+        }
+        self.previous = Some(stat);
+
+        m.ram_total_gb = memory.total_bytes as f32 / GIB;
+        m.ram_used_gb = memory.total_bytes.saturating_sub(memory.available_bytes) as f32 / GIB;
+        m.memory = Some(memory);
+        self.health = SourceHealth::Ok;
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    // Two readings 1000 jiffies apart, trimmed from a 6.x kernel.
+    const STAT_BEFORE: &str = "\
+cpu  1000 100 500 8000 200 10 20 0 0 0
+cpu0 600 50 300 3900 150 10 15 0 0 0
+cpu1 400 50 200 4100 50 0 5 0 0 0
+intr 123456 0 9 0
+ctxt 987654
+";
+    const STAT_AFTER: &str = "\
+cpu  1400 100 700 8280 250 20 30 50 40 0
+cpu0 900 50 400 3960 150 20 25 50 40 0
+cpu1 500 50 300 4320 100 0 5 0 0 0
+intr 123999 0 9 0
+ctxt 988000
+";
+
+    const MEMINFO: &str = "\
+MemTotal:       16318412 kB
+MemFree:         1032340 kB
+MemAvailable:    9876544 kB
+Buffers:          412304 kB
+Cached:          7831220 kB
+SwapCached:        10240 kB
+Dirty:              1236 kB
+SwapTotal:       2097148 kB
+SwapFree:        2000000 kB
+HugePages_Total:       0
+Hugepagesize:       2048 kB
+";
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn splits_time_per_mode() {
+        let before = parse_proc_stat(STAT_BEFORE).unwrap();
+        let after = parse_proc_stat(STAT_AFTER).unwrap();
+        assert_eq!(after.cpus.iter().map(|(n, _)| *n).collect::<Vec<_>>(), [0, 1]);
+        // guest (40) is part of user already and must not be added again.
+        assert_eq!(after.total.user, 1400);
+
+        let t = cpu_times(&before.total, &after.total);
+        assert!(close(t.user, 40.0) && close(t.system, 20.0) && close(t.nice, 0.0));
+        assert!(close(t.iowait, 5.0) && close(t.irq, 1.0) && close(t.softirq, 1.0) && close(t.steal, 5.0));
+        assert!(close(t.busy(), 67.0));
+    }
+
+    #[test]
+    fn old_kernel_without_steal_or_guest() {
+        // 2.6.0 had seven columns.
+        let stat = parse_proc_stat("cpu  100 2 30 400 5 0 1\ncpu0 100 2 30 400 5 0 1\n").unwrap();
+        assert_eq!(stat.total, CpuStat { user: 100, nice: 2, system: 30, idle: 400, iowait: 5, irq: 0, softirq: 1, steal: 0 });
+        assert!(parse_proc_stat("cpu0 1 2 3 4\n").is_none());
+    }
+
+    #[test]
+    fn identical_readings_are_idle_not_nan() {
+        let stat = parse_proc_stat(STAT_BEFORE).unwrap();
+        assert_eq!(cpu_times(&stat.total, &stat.total).busy(), 0.0);
+    }
+
+    #[test]
+    fn memory_breakdown() {
+        let info = parse_meminfo(MEMINFO);
+        assert_eq!(info["HugePages_Total"], 0);
+        let m = memory_detail(&info).unwrap();
+        assert_eq!(m.total_bytes, 16318412 * 1024);
+        assert_eq!(m.available_bytes, 9876544 * 1024);
+        assert_eq!(m.dirty_bytes, 1236 * 1024);
+        assert_eq!(m.swap_used_bytes, 97148 * 1024);
+        assert!(memory_detail(&parse_meminfo("MemFree: 1 kB\n")).is_none());
+    }
+
+    #[test]
+    fn estimates_available_before_3_14() {
+        let old: String = MEMINFO.lines().filter(|l| !l.starts_with("MemAvailable")).map(|l| format!("{}\n", l)).collect();
+        let m = memory_detail(&parse_meminfo(&old)).unwrap();
+        assert_eq!(m.available_bytes, (1032340 + 412304 + 7831220) * 1024);
+    }
+
+    #[test]
+    fn source_deltas_across_samples() {
+        let tree = TempTree::new();
+        let stat = tree.write("proc/stat", STAT_BEFORE);
+        let meminfo = tree.write("proc/meminfo", MEMINFO);
+        tree.write("sys/cpu0/topology/core_id", "0\n");
+        tree.write("sys/cpu0/topology/physical_package_id", "0\n");
+        tree.write("sys/cpu0/cpufreq/cpuinfo_max_freq", "4200000\n");
+        tree.write("sys/cpu0/cpufreq/scaling_cur_freq", "3600000\n");
+        // cpu1 has no topology, as in some VMs.
+
+        let mut source = ProcStatSource::with_paths(&stat, &meminfo, tree.path("sys"));
+        source.init().unwrap();
+        tree.write("proc/stat", STAT_AFTER);
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+
+        assert!(close(m.cpu_usage, 67.0));
+        assert_eq!(m.cores.len(), 2);
+        // cpu0: 300 + 100 + 10 + 10 + 50 busy of 530.
+        assert!(close(m.cores[0].usage, 470.0 / 530.0 * 100.0));
+        assert_eq!((m.cores[0].freq_mhz, m.cores[0].max_freq_mhz), (3600, 4200));
+        assert_eq!((m.cores[1].core_id, m.cores[1].package_id, m.cores[1].freq_mhz), (1, 0, 0));
+        assert!(close(m.ram_total_gb, 16318412.0 / 1024.0 / 1024.0));
+        assert_eq!(source.health(), SourceHealth::Ok);
+
+        // A second sample with unchanged counters is idle, not a repeat.
+        source.sample(&mut m);
+        assert_eq!(m.cpu_usage, 0.0);
+    }
+}
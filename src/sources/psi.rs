@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{HardwareMetrics, Pressure, PressureLine, Psi};
+
+// Parses a PSI file, from /proc/pressure or a cgroup's *.pressure:
+//
+//   some avg10=0.93 avg60=2.48 avg300=2.01 total=95569396
+//   full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+pub fn parse_pressure(text: &str) -> Option<Pressure> {
+    let mut some = None;
+    let mut full = None;
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let slot = match fields.next() {
+            Some("some") => &mut some,
+            Some("full") => &mut full,
+            _ => continue,
+        };
+        let mut parsed = PressureLine::default();
+        for field in fields {
+            match field.split_once('=') {
+                Some(("avg10", v)) => parsed.avg10 = v.parse().ok()?,
+                Some(("avg60", v)) => parsed.avg60 = v.parse().ok()?,
+                Some(("avg300", v)) => parsed.avg300 = v.parse().ok()?,
+                Some(("total", v)) => parsed.total_usec = v.parse().ok()?,
+                _ => {}
+            }
+        }
+        *slot = Some(parsed);
+    }
+    Some(Pressure { some: some?, full })
+}
+
+// Reads `cpu{suffix}`, `memory{suffix}` and `io{suffix}` from `dir`: an empty
+// suffix for /proc/pressure, ".pressure" for a cgroup directory.
+pub fn read_psi(dir: &Path, suffix: &str) -> Psi {
+    let read = |resource: &str| {
+        let text = std::fs::read_to_string(dir.join(format!("{}{}", resource, suffix))).ok()?;
+        parse_pressure(&text)
+    };
+    Psi { cpu: read("cpu"), memory: read("memory"), io: read("io") }
+}
+
+// System-wide pressure stall information from /proc/pressure.
+pub struct PsiSource {
+    dir: PathBuf,
+    health: SourceHealth,
+}
+
+impl PsiSource {
+    pub fn new() -> Self {
+        Self::with_path("/proc/pressure")
+    }
+
+    // Lets the source run against a fake procfs tree.
+    pub fn with_path(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), health: SourceHealth::Pending }
+    }
+}
+
+impl MetricSource for PsiSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "psi", description: "Pressure stall information (/proc/pressure)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        // Kernels built without CONFIG_PSI have no directory; booting with
+        // psi=0 leaves the files in place but fails every read.
+        if read_psi(&self.dir, "").is_empty() {
+            let e = format!("{}: not supported by this kernel", self.dir.display());
+            self.health = SourceHealth::Unavailable(e.clone());
+            return Err(e);
+        }
+        self.health = SourceHealth::Ok;
+        Ok(())
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        m.psi = read_psi(&self.dir, "");
+        self.health = if m.psi.is_empty() {
+            SourceHealth::Degraded(format!("{}: unreadable", self.dir.display()))
+        } else {
+            SourceHealth::Ok
+        };
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    #[test]
+    fn cpu_with_only_some() {
+        // Kernels before 5.13 have no "full" line for cpu.
+        let cpu = parse_pressure("some avg10=1.52 avg60=0.87 avg300=0.40 total=95569396\n").unwrap();
+        assert_eq!((cpu.some.avg10, cpu.some.avg60, cpu.some.avg300, cpu.some.total_usec), (1.52, 0.87, 0.40, 95569396));
+        assert!(cpu.full.is_none());
+    }
+
+    #[test]
+    fn some_and_full() {
+        let io = parse_pressure(
+            "some avg10=12.50 avg60=8.01 avg300=2.00 total=123456\nfull avg10=10.25 avg60=6.00 avg300=1.50 total=99999\n",
+        )
+        .unwrap();
+        assert_eq!(io.some.avg10, 12.5);
+        let full = io.full.unwrap();
+        assert_eq!((full.avg10, full.total_usec), (10.25, 99999));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        assert!(parse_pressure("").is_none());
+        assert!(parse_pressure("hello world\n").is_none());
+        // A "full" line alone isn't enough.
+        assert!(parse_pressure("full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n").is_none());
+        assert!(parse_pressure("some avg10=abc avg60=0.00 avg300=0.00 total=0\n").is_none());
+    }
+
+    #[test]
+    fn source_reads_every_resource() {
+        let tree = TempTree::new();
+        tree.write("pressure/cpu", "some avg10=3.00 avg60=2.00 avg300=1.00 total=100\n");
+        tree.write("pressure/io", "some avg10=20.00 avg60=0.00 avg300=0.00 total=5\nfull avg10=15.00 avg60=0.00 avg300=0.00 total=4\n");
+        // Unparseable content leaves the resource out rather than reading as zero.
+        tree.write("pressure/memory", "garbage\n");
+
+        let mut source = PsiSource::with_path(tree.path("pressure"));
+        source.init().unwrap();
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+        assert_eq!(m.psi.cpu.unwrap().some.avg10, 3.0);
+        assert_eq!(m.psi.io.unwrap().full.unwrap().avg10, 15.0);
+        assert!(m.psi.memory.is_none());
+        assert_eq!(source.health(), SourceHealth::Ok);
+
+        let mut missing = PsiSource::with_path(tree.path("nope"));
+        assert!(missing.init().is_err());
+    }
+}
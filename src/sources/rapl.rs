@@ -0,0 +1,202 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::{MetricSource, SourceHealth, SourceInfo};
+use crate::metrics::{HardwareMetrics, PowerDomain};
+
+// Microjoules used between two readings of a counter that wraps back to
+// zero after `max_range`. A reading above `max_range` means the range is
+// misreported; only what came after the wrap is counted then.
+pub fn energy_delta(before: u64, now: u64, max_range: u64) -> u64 {
+    if now >= before {
+        now - before
+    } else {
+        max_range.saturating_sub(before) + now
+    }
+}
+
+struct Zone {
+    dir: PathBuf,
+    domain: PowerDomain,
+    max_range_uj: u64,
+    previous_uj: u64,
+    // When previous_uj was read. Per zone, so a zone whose read failed gets
+    // its next delta divided by the full time since its last good read.
+    previous_at: Instant,
+}
+
+fn read_u64(path: &Path) -> Result<u64, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    text.trim().parse().map_err(|_| format!("{}: not a number", path.display()))
+}
+
+// Package, core, uncore and DRAM energy from the RAPL powercap interface,
+// turned into watts.
+pub struct RaplSource {
+    dir: PathBuf,
+    zones: Vec<Zone>,
+    health: SourceHealth,
+}
+
+impl RaplSource {
+    pub fn new() -> Self {
+        Self::with_path("/sys/class/powercap")
+    }
+
+    // Lets the source run against a fake sysfs tree.
+    pub fn with_path(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), zones: Vec::new(), health: SourceHealth::Pending }
+    }
+
+    // Zones are "intel-rapl:<package>" with subzones "intel-rapl:<package>:<n>";
+    // AMD's driver registers under the same names. "intel-rapl" itself is the
+    // control type, and "intel-rapl-mmio:*" reports the same packages a second
+    // time, so both are skipped.
+    fn discover(&self) -> Result<Vec<Zone>, String> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| format!("{}: {}", self.dir.display(), e))?;
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .filter(|name| name.starts_with("intel-rapl:"))
+            .collect();
+        names.sort();
+
+        let mut zones = Vec::new();
+        for zone in names {
+            let dir = self.dir.join(&zone);
+            let name = std::fs::read_to_string(dir.join("name")).map_err(|e| format!("{}: {}", dir.display(), e))?;
+            // The package zone is the one with a single index.
+            let package_zone = zone.splitn(3, ':').take(2).collect::<Vec<_>>().join(":");
+            let package = std::fs::read_to_string(self.dir.join(&package_zone).join("name"))
+                .map(|text| text.trim().to_string())
+                .unwrap_or_else(|_| package_zone.clone());
+            zones.push(Zone {
+                domain: PowerDomain { zone, name: name.trim().to_string(), package, ..Default::default() },
+                max_range_uj: read_u64(&dir.join("max_energy_range_uj"))?,
+                // Root-only since the PLATYPUS side channel; this is where
+                // running unprivileged fails.
+                previous_uj: read_u64(&dir.join("energy_uj"))?,
+                previous_at: Instant::now(),
+                dir,
+            });
+        }
+        if zones.is_empty() {
+            return Err(format!("{}: no RAPL zones", self.dir.display()));
+        }
+        Ok(zones)
+    }
+}
+
+impl MetricSource for RaplSource {
+    fn describe(&self) -> SourceInfo {
+        SourceInfo { id: "power", description: "CPU and DRAM power (RAPL powercap)" }
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        match self.discover() {
+            Ok(zones) => {
+                self.zones = zones;
+                self.health = SourceHealth::Ok;
+                Ok(())
+            }
+            Err(e) => {
+                self.health = SourceHealth::Unavailable(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn sample(&mut self, m: &mut HardwareMetrics) {
+        let mut error = None;
+        for zone in &mut self.zones {
+            let energy_uj = match read_u64(&zone.dir.join("energy_uj")) {
+                Ok(energy_uj) => energy_uj,
+                Err(e) => {
+                    error = Some(e);
+                    continue;
+                }
+            };
+            let now = Instant::now();
+            let elapsed = now.duration_since(zone.previous_at).as_secs_f64();
+            // A typical max range of ~262 kJ wraps within the hour under load;
+            // one wrap per sample interval is all this can undo.
+            let delta_uj = energy_delta(zone.previous_uj, energy_uj, zone.max_range_uj);
+            zone.previous_uj = energy_uj;
+            zone.previous_at = now;
+            zone.domain.energy_joules += delta_uj as f64 / 1e6;
+            if elapsed > 0.0 {
+                zone.domain.watts = (delta_uj as f64 / 1e6 / elapsed) as f32;
+            }
+        }
+        m.power = self.zones.iter().map(|zone| zone.domain.clone()).collect();
+        self.health = match error {
+            Some(e) => SourceHealth::Degraded(e),
+            None => SourceHealth::Ok,
+        };
+    }
+
+    fn health(&self) -> SourceHealth {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+    use std::time::Duration;
+
+    const MAX: u64 = 262_143_328_850;
+
+    #[test]
+    fn delta_without_wrap() {
+        assert_eq!(energy_delta(1_000, 251_000, MAX), 250_000);
+        assert_eq!(energy_delta(5, 5, MAX), 0);
+    }
+
+    #[test]
+    fn delta_across_a_wrap() {
+        assert_eq!(energy_delta(MAX - 100, 50, MAX), 150);
+        assert_eq!(energy_delta(MAX, 0, MAX), 0);
+    }
+
+    #[test]
+    fn delta_with_a_misreported_range() {
+        // The counter went past max_range before wrapping; count from zero.
+        assert_eq!(energy_delta(MAX + 10, 40, MAX), 40);
+    }
+
+    fn zone(tree: &TempTree, zone: &str, name: &str, energy_uj: u64) {
+        tree.write(&format!("powercap/{}/name", zone), &format!("{}\n", name));
+        tree.write(&format!("powercap/{}/max_energy_range_uj", zone), &format!("{}\n", MAX));
+        tree.write(&format!("powercap/{}/energy_uj", zone), &format!("{}\n", energy_uj));
+    }
+
+    #[test]
+    fn failed_read_does_not_double_the_next_reading() {
+        let tree = TempTree::new();
+        zone(&tree, "intel-rapl:0", "package-0", 0);
+        zone(&tree, "intel-rapl:0:0", "core", 0);
+        zone(&tree, "intel-rapl-mmio:0", "package-0", 0);
+        let mut source = RaplSource::with_path(tree.path("powercap"));
+        source.init().unwrap();
+
+        std::thread::sleep(Duration::from_millis(50));
+        std::fs::remove_file(tree.path("powercap/intel-rapl:0:0/energy_uj")).unwrap();
+        zone(&tree, "intel-rapl:0", "package-0", 50_000);
+        let mut m = HardwareMetrics::default();
+        source.sample(&mut m);
+        assert!(matches!(source.health(), SourceHealth::Degraded(_)));
+        assert_eq!(m.power.iter().map(|d| d.zone.as_str()).collect::<Vec<_>>(), ["intel-rapl:0", "intel-rapl:0:0"]);
+        assert_eq!(m.power[1].package, "package-0");
+
+        std::thread::sleep(Duration::from_millis(50));
+        // 0.1 J over at least 100 ms since the core zone's last good read.
+        zone(&tree, "intel-rapl:0:0", "core", 100_000);
+        source.sample(&mut m);
+        assert_eq!(source.health(), SourceHealth::Ok);
+        let core = &m.power[1];
+        assert!(core.watts > 0.0 && core.watts <= 1.0, "{} W", core.watts);
+        assert_eq!(core.energy_joules, 0.1);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::metrics::PcieLink;
+use crate::pcie;
+
+// Where the kernel links NVMe controllers to their PCI functions.
+pub const SYS_CLASS_NVME: &str = "/sys/class/nvme";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    // A bridge directly below a host bridge.
+    RootPort,
+    // A bridge below another bridge, i.e. a port of a PCIe switch.
+    SwitchPort,
+    Endpoint,
+}
+
+// One PCI function and everything behind it.
+pub struct PciNode {
+    // sysfs form, e.g. "0000:17:00.0".
+    pub address: String,
+    pub kind: NodeKind,
+    // 24-bit class code: base class, subclass, programming interface.
+    pub class: u32,
+    pub vendor: u16,
+    pub device: u16,
+    pub link: Option<PcieLink>,
+    // None on single-node machines, where the kernel reports -1.
+    pub numa_node: Option<u32>,
+    pub children: Vec<PciNode>,
+}
+
+impl PciNode {
+    pub fn class_name(&self) -> &'static str {
+        match (self.class >> 8, self.class) {
+            (_, 0x010802) => "NVMe controller",
+            (0x0106, _) => "SATA controller",
+            (0x0107, _) => "SAS controller",
+            (0x0104, _) => "RAID controller",
+            (0x0200, _) => "Ethernet controller",
+            (0x0207, _) => "InfiniBand controller",
+            (0x0300, _) => "VGA controller",
+            (0x0302, _) => "3D controller",
+            (0x0403, _) => "Audio device",
+            (0x0600, _) => "Host bridge",
+            (0x0604, _) => "PCI bridge",
+            (0x0c03, _) => "USB controller",
+            (0x1200, _) => "Processing accelerator",
+            _ => "Device",
+        }
+    }
+
+    pub fn vendor_name(&self) -> Option<&'static str> {
+        Some(match self.vendor {
+            0x10de => "NVIDIA",
+            0x1002 | 0x1022 => "AMD",
+            0x8086 => "Intel",
+            0x15b3 => "Mellanox",
+            0x10b5 => "Broadcom / PLX",
+            0x1000 => "Broadcom / LSI",
+            0x14e4 => "Broadcom",
+            0x144d => "Samsung",
+            0x1344 => "Micron",
+            0x15b7 => "Western Digital",
+            0x1e0f => "Kioxia",
+            0x1c5c => "SK hynix",
+            0x1d0f => "Amazon",
+            0x1af4 => "Red Hat (virtio)",
+            _ => return None,
+        })
+    }
+
+    // True if this node or anything below it has an address in `addresses`.
+    pub fn contains_any(&self, addresses: &HashMap<String, String>) -> bool {
+        addresses.contains_key(&self.address) || self.children.iter().any(|c| c.contains_any(addresses))
+    }
+}
+
+// Attributes of one function before the tree is assembled.
+struct Flat {
+    address: String,
+    parent: Option<String>,
+    class: u32,
+    vendor: u16,
+    device: u16,
+    link: Option<PcieLink>,
+    numa_node: Option<u32>,
+}
+
+// "0000:17:00.0": domain, bus, device and function. Host bridge directories
+// ("pci0000:00") and everything else sysfs puts next to them don't match.
+fn is_pci_address(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 12
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b'.'
+        && name.chars().enumerate().all(|(i, c)| matches!(i, 4 | 7 | 10) || c.is_ascii_hexdigit())
+}
+
+fn read_hex(dir: &Path, name: &str) -> Option<u32> {
+    let text = std::fs::read_to_string(dir.join(name)).ok()?;
+    u32::from_str_radix(text.trim().trim_start_matches("0x"), 16).ok()
+}
+
+fn read_device(sys_pci_devices: &Path, address: String) -> Flat {
+    // Entries in /sys/bus/pci/devices are links into /sys/devices, where
+    // each function sits in the directory of the bridge in front of it.
+    let entry = sys_pci_devices.join(&address);
+    let real = std::fs::canonicalize(&entry).unwrap_or(entry);
+    let parent = real
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .filter(|n| is_pci_address(n))
+        .map(str::to_string);
+    let numa_node = std::fs::read_to_string(real.join("numa_node"))
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .filter(|&n| n >= 0)
+        .map(|n| n as u32);
+    Flat {
+        parent,
+        class: read_hex(&real, "class").unwrap_or(0),
+        vendor: read_hex(&real, "vendor").unwrap_or(0) as u16,
+        device: read_hex(&real, "device").unwrap_or(0) as u16,
+        link: pcie::sysfs_link(sys_pci_devices, &address),
+        numa_node,
+        address,
+    }
+}
+
+fn build(address: &str, flat: &HashMap<String, Flat>, children: &HashMap<String, Vec<String>>) -> PciNode {
+    let f = &flat[address];
+    let kids: Vec<PciNode> = children.get(address).into_iter().flatten().map(|c| build(c, flat, children)).collect();
+    let kind = match (f.class >> 8 == 0x0604, f.parent.is_some()) {
+        (true, false) => NodeKind::RootPort,
+        (true, true) => NodeKind::SwitchPort,
+        (false, _) => NodeKind::Endpoint,
+    };
+    PciNode {
+        address: f.address.clone(),
+        kind,
+        class: f.class,
+        vendor: f.vendor,
+        device: f.device,
+        link: f.link,
+        numa_node: f.numa_node,
+        children: kids,
+    }
+}
+
+// Builds the PCI tree from a sysfs `bus/pci/devices` directory. Returns the
+// functions directly below the host bridges (root ports and integrated
+// devices), each with its subtree, ordered by address.
+pub fn scan(sys_pci_devices: &Path) -> Result<Vec<PciNode>, String> {
+    let entries = std::fs::read_dir(sys_pci_devices).map_err(|e| format!("{}: {}", sys_pci_devices.display(), e))?;
+    let flat: HashMap<String, Flat> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            is_pci_address(&name).then(|| (name.clone(), read_device(sys_pci_devices, name)))
+        })
+        .collect();
+
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    let mut roots = Vec::new();
+    for f in flat.values() {
+        match &f.parent {
+            // A parent missing from the listing (filtered by a container's
+            // sysfs view) makes the device a root of its own.
+            Some(parent) if flat.contains_key(parent) => children.entry(parent.clone()).or_default().push(f.address.clone()),
+            _ => roots.push(f.address.clone()),
+        }
+    }
+    for list in children.values_mut() {
+        list.sort();
+    }
+    roots.sort();
+    Ok(roots.iter().map(|root| build(root, &flat, &children)).collect())
+}
+
+// The scanned tree together with what's needed to tag it on every frame
+// without going back to sysfs.
+pub struct Topology {
+    pub roots: Vec<PciNode>,
+    // NVMe controller name ("nvme0") -> PCI address of its function.
+    controllers: HashMap<String, String>,
+}
+
+impl Topology {
+    pub fn scan(sys_pci_devices: &Path, sys_class_nvme: &Path) -> Result<Self, String> {
+        Ok(Self { roots: scan(sys_pci_devices)?, controllers: nvme_controllers(sys_class_nvme) })
+    }
+
+    // PCI address of the controller behind an NVMe block device such as
+    // "nvme0n1" or "nvme1c1n1".
+    pub fn nvme_address(&self, disk: &str) -> Option<&str> {
+        let digits = disk.strip_prefix("nvme")?;
+        let controller = &disk[..4 + digits.find(|c: char| !c.is_ascii_digit())?];
+        self.controllers.get(controller).map(String::as_str)
+    }
+}
+
+// Every controller in /sys/class/nvme whose device link points at a PCI
+// function. Fabrics controllers (TCP, RDMA) don't.
+fn nvme_controllers(sys_class_nvme: &Path) -> HashMap<String, String> {
+    std::fs::read_dir(sys_class_nvme)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let device: PathBuf = std::fs::canonicalize(entry.path().join("device")).ok()?;
+            let address = device.file_name()?.to_str().filter(|n| is_pci_address(n))?.to_string();
+            Some((entry.file_name().to_str()?.to_string(), address))
+        })
+        .collect()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::test_util::TempTree;
+
+    // Adds a function below `parent` in devices/ and links it from
+    // bus/pci/devices the way sysfs does.
+    fn function(tree: &TempTree, parent: &str, address: &str, class: &str, vendor: &str) -> String {
+        let dir = format!("{}/{}", parent, address);
+        tree.write(&format!("{}/class", dir), &format!("{}\n", class));
+        tree.write(&format!("{}/vendor", dir), &format!("{}\n", vendor));
+        tree.write(&format!("{}/device", dir), "0x1234\n");
+        tree.write(&format!("{}/numa_node", dir), "-1\n");
+        tree.symlink(&format!("bus/pci/devices/{}", address), &format!("../../../{}", dir));
+        dir
+    }
+
+    fn link(tree: &TempTree, dir: &str, speed: &str, width: &str, max_speed: &str, max_width: &str) {
+        tree.write(&format!("{}/current_link_speed", dir), speed);
+        tree.write(&format!("{}/current_link_width", dir), width);
+        tree.write(&format!("{}/max_link_speed", dir), max_speed);
+        tree.write(&format!("{}/max_link_width", dir), max_width);
+    }
+
+    // Root port -> switch upstream port -> downstream port -> NVMe drive,
+    // next to an integrated device straight on the host bridge.
+    fn machine() -> TempTree {
+        let tree = TempTree::new();
+        let root = function(&tree, "devices/pci0000:00", "0000:00:01.1", "0x060400", "0x1022");
+        let upstream = function(&tree, &root, "0000:01:00.0", "0x060400", "0x10b5");
+        let downstream = function(&tree, &upstream, "0000:02:08.0", "0x060400", "0x10b5");
+        let drive = function(&tree, &downstream, "0000:03:00.0", "0x010802", "0x144d");
+        function(&tree, "devices/pci0000:00", "0000:00:14.0", "0x0c0330", "0x8086");
+        link(&tree, &root, "16.0 GT/s PCIe\n", "16\n", "16.0 GT/s PCIe\n", "16\n");
+        // Trained below its capability.
+        link(&tree, &drive, "8.0 GT/s PCIe\n", "2\n", "16.0 GT/s PCIe\n", "4\n");
+        tree.write(&format!("{}/numa_node", drive), "1\n");
+        tree.symlink("class/nvme/nvme0/device", &format!("../../../{}", drive));
+        tree.mkdir("class/nvme/nvme1");
+        tree
+    }
+
+    #[test]
+    fn nests_bridges_and_endpoints() {
+        let tree = machine();
+        let roots = scan(&tree.path("bus/pci/devices")).unwrap();
+        assert_eq!(roots.iter().map(|n| n.address.as_str()).collect::<Vec<_>>(), ["0000:00:01.1", "0000:00:14.0"]);
+        assert!(roots[0].kind == NodeKind::RootPort);
+        assert!(roots[1].kind == NodeKind::Endpoint && roots[1].children.is_empty());
+        assert_eq!(roots[1].class_name(), "USB controller");
+
+        let upstream = &roots[0].children[0];
+        let downstream = &upstream.children[0];
+        let drive = &downstream.children[0];
+        assert!(upstream.kind == NodeKind::SwitchPort && downstream.kind == NodeKind::SwitchPort);
+        assert_eq!(downstream.address, "0000:02:08.0");
+        assert!(drive.kind == NodeKind::Endpoint && drive.children.is_empty());
+        assert_eq!((drive.class_name(), drive.vendor_name(), drive.device), ("NVMe controller", Some("Samsung"), 0x1234));
+        assert_eq!((drive.numa_node, upstream.numa_node), (Some(1), None));
+    }
+
+    #[test]
+    fn parses_link_speed_and_width() {
+        let tree = machine();
+        let roots = scan(&tree.path("bus/pci/devices")).unwrap();
+        let root = roots[0].link.unwrap();
+        assert_eq!((root.gen, root.width, root.max_gen, root.max_width), (4, 16, 4, 16));
+        let drive = roots[0].children[0].children[0].children[0].link.unwrap();
+        assert_eq!((drive.gen, drive.width, drive.max_gen, drive.max_width), (3, 2, 4, 4));
+        // Switch ports in the fixture have no link attributes.
+        assert!(roots[0].children[0].link.is_none());
+        assert_eq!(pcie::parse_link_speed("2.5 GT/s"), Some(1));
+        assert_eq!(pcie::parse_link_speed("Unknown"), None);
+    }
+
+    #[test]
+    fn maps_nvme_disks_to_their_controller() {
+        let tree = machine();
+        let topology = Topology::scan(&tree.path("bus/pci/devices"), &tree.path("class/nvme")).unwrap();
+        assert_eq!(topology.nvme_address("nvme0n1"), Some("0000:03:00.0"));
+        assert_eq!(topology.nvme_address("nvme0c0n2"), Some("0000:03:00.0"));
+        // No device link, as for fabrics controllers.
+        assert_eq!(topology.nvme_address("nvme1n1"), None);
+        assert_eq!(topology.nvme_address("sda"), None);
+        assert!(topology.roots[0].contains_any(&HashMap::from([("0000:03:00.0".to_string(), "nvme0n1".to_string())])));
+    }
+}
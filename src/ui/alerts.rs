@@ -0,0 +1,22 @@
+use eframe::egui;
+
+use crate::alerts::{AlertEvent, Severity};
+
+fn severity_color(severity: Severity) -> egui::Color32 {
+    match severity {
+        Severity::Info => egui::Color32::from_rgb(90, 170, 250),
+        Severity::Warning => egui::Color32::from_rgb(230, 140, 40),
+        Severity::Critical => egui::Color32::from_rgb(220, 60, 60),
+    }
+}
+
+// One line per firing rule, worst first. Lines disappear when the rule resolves.
+pub fn alert_banner(ui: &mut egui::Ui, firing: &[AlertEvent]) {
+    for event in firing {
+        ui.horizontal(|ui| {
+            let color = severity_color(event.severity);
+            ui.label(egui::RichText::new(event.severity.label()).strong().color(color));
+            ui.label(egui::RichText::new(&event.message).color(color));
+        });
+    }
+}
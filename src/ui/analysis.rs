@@ -0,0 +1,37 @@
+use eframe::egui;
+
+use crate::analysis::{State, Verdict};
+
+fn state_color(state: State) -> egui::Color32 {
+    match state {
+        State::DiskBound | State::PcieBound | State::CpuBound | State::MemoryPressure => egui::Color32::from_rgb(230, 140, 40),
+        State::Idle | State::Balanced => egui::Color32::from_rgb(90, 180, 90),
+        State::Unknown => egui::Color32::GRAY,
+    }
+}
+
+pub fn verdict_panel(ui: &mut egui::Ui, verdict: &Verdict) {
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(verdict.state.label()).strong().size(18.0).color(state_color(verdict.state)));
+        if verdict.state != State::Unknown {
+            ui.add(
+                egui::ProgressBar::new(verdict.confidence)
+                    .desired_width(140.0)
+                    .text(format!("{:.0}% confidence", verdict.confidence * 100.0)),
+            );
+        }
+    });
+    ui.label(&verdict.explanation);
+
+    if !verdict.scores.is_empty() {
+        egui::CollapsingHeader::new("All candidates").id_salt("verdict_scores").show(ui, |ui| {
+            egui::Grid::new("verdict_scores_grid").show(ui, |ui| {
+                for (state, score) in &verdict.scores {
+                    ui.label(state.label());
+                    ui.add(egui::ProgressBar::new(*score).desired_width(120.0).text(format!("{:.0}%", score * 100.0)));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
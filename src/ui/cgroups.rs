@@ -0,0 +1,90 @@
+use eframe::egui;
+use std::collections::HashMap;
+
+use crate::config::BandwidthUnit;
+use super::pressure::pressure_details;
+use crate::metrics::CgroupMetrics;
+
+const PINNED: egui::Color32 = egui::Color32::from_rgb(90, 170, 250);
+
+fn summary(cgroup: &CgroupMetrics, units: BandwidthUnit) -> String {
+    let gb = |bytes: u64| bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+    let memory = match cgroup.memory_max_bytes {
+        Some(max) => format!("{:.2}/{:.2} GB", gb(cgroup.memory_bytes), gb(max)),
+        None => format!("{:.2} GB", gb(cgroup.memory_bytes)),
+    };
+    let mut summary = format!(
+        "CPU {:.1}%  mem {}  R {}  W {}",
+        cgroup.cpu_pct,
+        memory,
+        units.format(cgroup.read_bps),
+        units.format(cgroup.write_bps)
+    );
+    // The busiest resource's 10 s "some" pressure; the hover has the rest.
+    let stalled = [&cgroup.psi.cpu, &cgroup.psi.memory, &cgroup.psi.io]
+        .into_iter()
+        .flatten()
+        .map(|p| p.some.avg10)
+        .fold(None, |max: Option<f32>, v| Some(max.map_or(v, |m| m.max(v))));
+    if let Some(stalled) = stalled {
+        summary.push_str(&format!("  stall {:.1}%", stalled));
+    }
+    summary
+}
+
+fn header(ui: &mut egui::Ui, cgroup: &CgroupMetrics, pinned: &mut Option<String>, units: BandwidthUnit) {
+    let is_pinned = pinned.as_deref() == Some(cgroup.path.as_str());
+    let mut name = egui::RichText::new(cgroup.name()).monospace();
+    if is_pinned {
+        name = name.color(PINNED).strong();
+    }
+    ui.label(name).on_hover_text(&cgroup.path);
+    ui.weak(summary(cgroup, units)).on_hover_ui(|ui| {
+        for io in &cgroup.io {
+            ui.label(format!("{}: R {}  W {}", io.device, units.format(io.read_bps), units.format(io.write_bps)));
+        }
+        if !cgroup.psi.is_empty() {
+            pressure_details(ui, &cgroup.psi);
+        }
+    });
+    if ui.small_button(if is_pinned { "Unpin" } else { "Pin" }).clicked() {
+        *pinned = if is_pinned { None } else { Some(cgroup.path.clone()) };
+    }
+}
+
+// parent path -> children, in the order the source listed them.
+type Children<'a> = HashMap<&'a str, Vec<&'a CgroupMetrics>>;
+
+fn node(ui: &mut egui::Ui, children: &Children, cgroup: &CgroupMetrics, pinned: &mut Option<String>, units: BandwidthUnit) {
+    let Some(kids) = children.get(cgroup.path.as_str()) else {
+        ui.horizontal(|ui| header(ui, cgroup, pinned, units));
+        return;
+    };
+    // The branch holding the pinned cgroup starts open so it can be found again.
+    let open = pinned.as_deref().is_some_and(|p| cgroup.contains(p) && p != cgroup.path);
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), ui.make_persistent_id(&cgroup.path), open)
+        .show_header(ui, |ui| header(ui, cgroup, pinned, units))
+        .body(|ui| {
+            for child in kids {
+                node(ui, children, child, pinned, units);
+            }
+        });
+}
+
+// The cgroup hierarchy with per-cgroup usage. `cgroups` is flat with parents
+// before children, as the source reports it; `pinned` is the cgroup the main
+// view is scoped to.
+pub fn cgroup_tree(ui: &mut egui::Ui, cgroups: &[CgroupMetrics], pinned: &mut Option<String>, units: BandwidthUnit) {
+    // Built once per frame; a busy container host has thousands of cgroups.
+    let mut children: Children = HashMap::new();
+    let mut roots = Vec::new();
+    for cgroup in cgroups {
+        match cgroup.parent() {
+            Some(parent) => children.entry(parent).or_default().push(cgroup),
+            None => roots.push(cgroup),
+        }
+    }
+    for cgroup in roots {
+        node(ui, &children, cgroup, pinned, units);
+    }
+}
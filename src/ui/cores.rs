@@ -0,0 +1,83 @@
+use eframe::egui;
+use std::collections::BTreeMap;
+
+use crate::metrics::CoreMetrics;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum HeatmapMode {
+    #[default]
+    Utilization,
+    Frequency,
+}
+
+const CELL_SIZE: egui::Vec2 = egui::vec2(64.0, 30.0);
+
+// Dark blue for 0 through orange to red for 1.
+fn heat_color(t: f32) -> egui::Color32 {
+    let t = t.clamp(0.0, 1.0);
+    let stops = [(0.0, [40, 60, 110]), (0.5, [230, 140, 40]), (1.0, [220, 50, 40])];
+    let (lo, hi) = if t <= 0.5 { (stops[0], stops[1]) } else { (stops[1], stops[2]) };
+    let f = (t - lo.0) / (hi.0 - lo.0);
+    let mix = |i: usize| (lo.1[i] as f32 + (hi.1[i] as f32 - lo.1[i] as f32) * f) as u8;
+    egui::Color32::from_rgb(mix(0), mix(1), mix(2))
+}
+
+// One cell per logical CPU, with the SMT siblings of a physical core stacked
+// in one column, so a single saturated thread stands out even when the global
+// average is low.
+pub fn core_heatmap(ui: &mut egui::Ui, cores: &[CoreMetrics], mode: &mut HeatmapMode) {
+    ui.horizontal(|ui| {
+        ui.label("Color by:");
+        ui.selectable_value(mode, HeatmapMode::Utilization, "Utilization");
+        ui.selectable_value(mode, HeatmapMode::Frequency, "Frequency");
+    });
+
+    // Frequencies are shaded against the fastest clock any core can reach,
+    // or the fastest one seen right now if the limit is unknown.
+    let freq_scale = cores
+        .iter()
+        .map(|c| c.max_freq_mhz.max(c.freq_mhz))
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+
+    let mut physical: BTreeMap<(u32, u32), Vec<&CoreMetrics>> = BTreeMap::new();
+    for core in cores {
+        physical.entry((core.package_id, core.core_id)).or_default().push(core);
+    }
+    let multi_package = physical.keys().any(|&(package, _)| package > 0);
+
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing = egui::vec2(3.0, 3.0);
+        for ((package, core_id), siblings) in &physical {
+            ui.vertical(|ui| {
+                for core in siblings {
+                    let t = match mode {
+                        HeatmapMode::Utilization => core.usage / 100.0,
+                        HeatmapMode::Frequency => core.freq_mhz as f32 / freq_scale,
+                    };
+                    let (rect, response) = ui.allocate_exact_size(CELL_SIZE, egui::Sense::hover());
+                    let painter = ui.painter();
+                    painter.rect_filled(rect, 3.0, heat_color(t));
+                    let text = if core.freq_mhz > 0 {
+                        format!("{:.0}%\n{:.2} GHz", core.usage, core.freq_mhz as f32 / 1000.0)
+                    } else {
+                        format!("{:.0}%", core.usage)
+                    };
+                    painter.text(
+                        rect.center(),
+                        egui::Align2::CENTER_CENTER,
+                        text,
+                        egui::FontId::proportional(11.0),
+                        egui::Color32::WHITE,
+                    );
+                    response.on_hover_text(if multi_package {
+                        format!("CPU {} (package {}, core {})", core.cpu, package, core_id)
+                    } else {
+                        format!("CPU {} (core {})", core.cpu, core_id)
+                    });
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,116 @@
+use eframe::egui;
+use std::cmp::Ordering;
+
+use crate::config::BandwidthUnit;
+use crate::metrics::DiskDeviceMetrics;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DiskColumn {
+    Name,
+    Read,
+    Write,
+    ReadIops,
+    WriteIops,
+    Latency,
+    Queue,
+    Busy,
+}
+
+impl DiskColumn {
+    const ALL: [DiskColumn; 8] = [
+        DiskColumn::Name,
+        DiskColumn::Read,
+        DiskColumn::Write,
+        DiskColumn::ReadIops,
+        DiskColumn::WriteIops,
+        DiskColumn::Latency,
+        DiskColumn::Queue,
+        DiskColumn::Busy,
+    ];
+
+    fn title(self, units: BandwidthUnit) -> String {
+        match self {
+            DiskColumn::Name => "Device".to_string(),
+            DiskColumn::Read => format!("Read {}", units.label()),
+            DiskColumn::Write => format!("Write {}", units.label()),
+            DiskColumn::ReadIops => "Read IOPS".to_string(),
+            DiskColumn::WriteIops => "Write IOPS".to_string(),
+            DiskColumn::Latency => "Latency ms".to_string(),
+            DiskColumn::Queue => "Queue".to_string(),
+            DiskColumn::Busy => "Busy %".to_string(),
+        }
+    }
+
+    fn compare(self, a: &DiskDeviceMetrics, b: &DiskDeviceMetrics) -> Ordering {
+        let by = |x: f32, y: f32| x.partial_cmp(&y).unwrap_or(Ordering::Equal);
+        match self {
+            DiskColumn::Name => a.name.cmp(&b.name),
+            DiskColumn::Read => a.read_bps.cmp(&b.read_bps),
+            DiskColumn::Write => a.write_bps.cmp(&b.write_bps),
+            DiskColumn::ReadIops => by(a.read_iops, b.read_iops),
+            DiskColumn::WriteIops => by(a.write_iops, b.write_iops),
+            DiskColumn::Latency => by(a.avg_latency_ms, b.avg_latency_ms),
+            DiskColumn::Queue => by(a.queue_depth, b.queue_depth),
+            DiskColumn::Busy => by(a.busy_pct, b.busy_pct),
+        }
+    }
+}
+
+// Which column the disk table is sorted by. Lives in MonitorApp so the choice
+// survives repaints.
+pub struct DiskSort {
+    column: DiskColumn,
+    descending: bool,
+}
+
+impl Default for DiskSort {
+    fn default() -> Self {
+        Self { column: DiskColumn::Name, descending: false }
+    }
+}
+
+impl DiskSort {
+    // Clicking the active column flips the direction; clicking another one
+    // sorts by it, busiest first for the numeric columns.
+    fn click(&mut self, column: DiskColumn) {
+        if self.column == column {
+            self.descending = !self.descending;
+        } else {
+            self.column = column;
+            self.descending = column != DiskColumn::Name;
+        }
+    }
+}
+
+pub fn disk_table(ui: &mut egui::Ui, disks: &[DiskDeviceMetrics], sort: &mut DiskSort, units: BandwidthUnit) {
+    let mut rows: Vec<&DiskDeviceMetrics> = disks.iter().collect();
+    rows.sort_by(|a, b| {
+        let ord = sort.column.compare(a, b);
+        if sort.descending { ord.reverse() } else { ord }
+    });
+
+    egui::Grid::new("disk_table").striped(true).num_columns(DiskColumn::ALL.len()).show(ui, |ui| {
+        for column in DiskColumn::ALL {
+            let mut title = column.title(units);
+            if sort.column == column {
+                title.push_str(if sort.descending { " ⬇" } else { " ⬆" });
+            }
+            if ui.selectable_label(sort.column == column, title).clicked() {
+                sort.click(column);
+            }
+        }
+        ui.end_row();
+
+        for disk in rows {
+            ui.label(&disk.name);
+            ui.label(format!("{:.2}", units.convert(disk.read_bps as f64)));
+            ui.label(format!("{:.2}", units.convert(disk.write_bps as f64)));
+            ui.label(format!("{:.0}", disk.read_iops));
+            ui.label(format!("{:.0}", disk.write_iops));
+            ui.label(format!("{:.2}", disk.avg_latency_ms));
+            ui.label(format!("{:.2}", disk.queue_depth));
+            ui.label(format!("{:.1}", disk.busy_pct));
+            ui.end_row();
+        }
+    });
+}
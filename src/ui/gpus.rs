@@ -0,0 +1,91 @@
+use eframe::egui;
+
+use crate::config::BandwidthUnit;
+use crate::metrics::GpuMetrics;
+
+const CARD_WIDTH: f32 = 220.0;
+const WARNING: egui::Color32 = egui::Color32::from_rgb(230, 140, 40);
+const BUSY_UTIL_PCT: u32 = 20;
+
+// One framed card per GPU, wrapping onto as many rows as the window needs.
+// The aggregate over all cards is shown separately by the caller.
+pub fn gpu_cards(ui: &mut egui::Ui, gpus: &[GpuMetrics], units: BandwidthUnit) {
+    ui.horizontal_wrapped(|ui| {
+        for gpu in gpus {
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.set_width(CARD_WIDTH);
+                ui.vertical(|ui| {
+                    ui.strong(format!("GPU {}: {}", gpu.index, gpu.name));
+                    if !gpu.pci_bus_id.is_empty() {
+                        ui.weak(&gpu.pci_bus_id).on_hover_text(&gpu.uuid);
+                    }
+                    let vram = if gpu.vram_total_mb > 0 { gpu.vram_used_mb as f32 / gpu.vram_total_mb as f32 } else { 0.0 };
+                    ui.add(
+                        egui::ProgressBar::new(vram)
+                            .desired_width(CARD_WIDTH)
+                            .text(format!("VRAM {}/{} MB", gpu.vram_used_mb, gpu.vram_total_mb)),
+                    );
+                    if let Some(util) = gpu.util_pct {
+                        ui.add(
+                            egui::ProgressBar::new(util as f32 / 100.0)
+                                .desired_width(CARD_WIDTH)
+                                .text(format!("SM {}%  mem {}%", util, gpu.mem_util_pct.unwrap_or(0))),
+                        );
+                    }
+                    ui.label(format!("PCIe TX: {}", units.format(gpu.pcie_tx)));
+                    ui.label(format!("PCIe RX: {}", units.format(gpu.pcie_rx)));
+                    if let Some(link) = &gpu.pcie_link {
+                        ui.label(format!("Link: Gen{} x{} (max Gen{} x{})", link.gen, link.width, link.max_gen, link.max_width));
+                        // Cards drop to a lower generation when idle, so a slow link
+                        // only matters while the GPU is working.
+                        let busy = gpu.util_pct.is_some_and(|u| u >= BUSY_UTIL_PCT);
+                        if link.narrower_than_max() {
+                            ui.colored_label(WARNING, format!("Trained at x{}, below its x{} maximum", link.width, link.max_width));
+                        } else if link.slower_than_max() && busy {
+                            ui.colored_label(WARNING, format!("Running at Gen{} under load, below Gen{}", link.gen, link.max_gen));
+                        }
+                        if let (Some(pct), Some(ceiling)) = (gpu.pcie_util_pct(), link.bandwidth_bps()) {
+                            ui.add(
+                                egui::ProgressBar::new(pct / 100.0)
+                                    .desired_width(CARD_WIDTH)
+                                    .text(format!("PCIe {:.0}% of {:.1} GB/s", pct, ceiling / 1e9)),
+                            );
+                        }
+                    }
+                    if let Some(graphics) = gpu.graphics_clock_mhz {
+                        let max = |clock: Option<u32>| clock.map_or(String::new(), |c| format!("/{}", c));
+                        ui.label(format!(
+                            "Clocks: {}{} MHz, mem {}{} MHz",
+                            graphics,
+                            max(gpu.max_graphics_clock_mhz),
+                            gpu.memory_clock_mhz.unwrap_or(0),
+                            max(gpu.max_memory_clock_mhz)
+                        ));
+                    }
+                    let mut sensors = Vec::new();
+                    if let Some(temp) = gpu.temperature_c {
+                        sensors.push(format!("{} °C", temp));
+                    }
+                    if let Some(power) = gpu.power_w {
+                        sensors.push(match gpu.power_limit_w {
+                            Some(limit) if limit > 0.0 => format!("{:.0}/{:.0} W", power, limit),
+                            _ => format!("{:.0} W", power),
+                        });
+                    }
+                    if !sensors.is_empty() {
+                        ui.weak(sensors.join("  "));
+                    }
+                    if !gpu.throttle_reasons.is_empty() {
+                        let reasons: Vec<&str> = gpu.throttle_reasons.iter().map(|r| r.label()).collect();
+                        let text = format!("Throttled: {}", reasons.join(", "));
+                        if gpu.throttle_reasons.iter().any(|r| r.is_limiting()) {
+                            ui.colored_label(WARNING, text);
+                        } else {
+                            ui.weak(text);
+                        }
+                    }
+                });
+            });
+        }
+    });
+}
@@ -0,0 +1,33 @@
+// Reusable panels drawn by MonitorApp::update.
+
+mod alerts;
+mod analysis;
+mod cgroups;
+mod cores;
+mod disks;
+mod gpus;
+mod network;
+mod nvme;
+mod plots;
+mod power;
+mod pressure;
+mod processes;
+mod replay;
+mod sensors;
+mod topology;
+
+pub use alerts::alert_banner;
+pub use analysis::verdict_panel;
+pub use cgroups::cgroup_tree;
+pub use cores::{core_heatmap, HeatmapMode};
+pub use disks::{disk_table, DiskSort};
+pub use gpus::gpu_cards;
+pub use network::network_table;
+pub use nvme::nvme_health;
+pub use plots::history_plots;
+pub use power::power_summary;
+pub use pressure::pressure_summary;
+pub use processes::{process_table, ProcessView};
+pub use replay::replay_controls;
+pub use sensors::sensor_panel;
+pub use topology::topology_tree;
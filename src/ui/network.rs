@@ -0,0 +1,43 @@
+use eframe::egui;
+
+use crate::config::BandwidthUnit;
+use crate::metrics::NetInterfaceMetrics;
+
+fn link_speed(mbps: u32) -> String {
+    match mbps {
+        0 => "-".to_string(),
+        m if m >= 1000 => format!("{} Gb/s", m as f32 / 1000.0),
+        m => format!("{} Mb/s", m),
+    }
+}
+
+pub fn network_table(ui: &mut egui::Ui, interfaces: &[NetInterfaceMetrics], units: BandwidthUnit) {
+    egui::Grid::new("network_table").striped(true).num_columns(8).show(ui, |ui| {
+        ui.strong("Interface");
+        ui.strong("Link");
+        ui.strong(format!("RX {}", units.label()));
+        ui.strong(format!("TX {}", units.label()));
+        ui.strong("RX pkt/s");
+        ui.strong("TX pkt/s");
+        ui.strong("Link %");
+        ui.strong("Errors / Drops");
+        ui.end_row();
+
+        for iface in interfaces {
+            ui.label(&iface.name);
+            ui.label(link_speed(iface.link_speed_mbps));
+            ui.label(format!("{:.2}", units.convert(iface.rx_bps as f64)));
+            ui.label(format!("{:.2}", units.convert(iface.tx_bps as f64)));
+            ui.label(format!("{:.0}", iface.rx_pps));
+            ui.label(format!("{:.0}", iface.tx_pps));
+            ui.label(iface.utilization_pct().map_or("-".to_string(), |pct| format!("{:.1}", pct)));
+            // Totals since boot: any non-zero value is worth a look, rates would hide slow leaks.
+            ui.label(format!(
+                "{} / {}",
+                iface.rx_errors_total + iface.tx_errors_total,
+                iface.rx_drops_total + iface.tx_drops_total
+            ));
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,81 @@
+use eframe::egui;
+
+use crate::metrics::NvmeController;
+
+const WARNING: egui::Color32 = egui::Color32::from_rgb(230, 140, 40);
+const CRITICAL: egui::Color32 = egui::Color32::from_rgb(220, 60, 60);
+
+fn temperature(ui: &mut egui::Ui, controller: &NvmeController) {
+    // The hwmon reading, or the log's if the driver registered no chip.
+    let celsius = controller.temperature_c.or_else(|| controller.smart.as_ref().ok().map(|s| s.temperature_c as f64));
+    let Some(celsius) = celsius else {
+        ui.label("-");
+        return;
+    };
+    let mut text = egui::RichText::new(format!("{:.0} °C", celsius));
+    if controller.temperature_crit_c.is_some_and(|crit| celsius >= crit) {
+        text = text.color(CRITICAL).strong();
+    }
+    ui.label(text);
+}
+
+// One row per controller: identity from sysfs, then the health log fields,
+// or why the log couldn't be read.
+pub fn nvme_health(ui: &mut egui::Ui, controllers: &[NvmeController]) {
+    egui::Grid::new("nvme_health").striped(true).num_columns(9).show(ui, |ui| {
+        for title in ["Controller", "Model", "Firmware", "Transport", "Temp", "Used %", "Spare %", "Media errors", "Warnings"] {
+            ui.strong(title);
+        }
+        ui.end_row();
+
+        for controller in controllers {
+            let mut name = egui::RichText::new(&controller.name);
+            if controller.state != "live" {
+                name = name.color(WARNING);
+            }
+            ui.label(name).on_hover_text(format!(
+                "state: {}\nserial: {}\naddress: {}",
+                controller.state, controller.serial, controller.address
+            ));
+            ui.label(&controller.model);
+            ui.label(&controller.firmware);
+            ui.label(&controller.transport);
+            temperature(ui, controller);
+            match &controller.smart {
+                Ok(smart) => {
+                    let used = egui::RichText::new(smart.percentage_used.to_string());
+                    ui.label(if smart.percentage_used >= 100 { used.color(WARNING) } else { used });
+                    let spare = egui::RichText::new(format!(
+                        "{} (min {})",
+                        smart.available_spare_pct, smart.available_spare_threshold_pct
+                    ));
+                    ui.label(if smart.spare_low() { spare.color(CRITICAL) } else { spare });
+                    let errors = egui::RichText::new(smart.media_errors.to_string());
+                    ui.label(if smart.media_errors > 0 { errors.color(WARNING) } else { errors });
+                    let warnings = smart.warnings();
+                    let label = if warnings.is_empty() {
+                        egui::RichText::new("none")
+                    } else {
+                        egui::RichText::new(warnings.join(", ")).color(CRITICAL)
+                    };
+                    let gb = |bytes: u64| bytes as f64 / 1e9;
+                    ui.label(label).on_hover_text(format!(
+                        "read {:.0} GB, written {:.0} GB\npower-on {} h, unsafe shutdowns {}\nerror log entries {}",
+                        gb(smart.data_read_bytes),
+                        gb(smart.data_written_bytes),
+                        smart.power_on_hours,
+                        smart.unsafe_shutdowns,
+                        smart.error_log_entries
+                    ));
+                }
+                Err(e) => {
+                    for _ in 0..3 {
+                        ui.label("-");
+                    }
+                    ui.weak("SMART unavailable").on_hover_text(e);
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,78 @@
+use eframe::egui;
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+
+use crate::config::BandwidthUnit;
+use crate::history::History;
+use crate::metrics::{HardwareMetrics, Metric};
+
+// Metrics that share a unit and belong together are drawn on one plot.
+// Pressure sits next to the matching utilization: a resource can be far from
+// 100% busy and still keep tasks waiting.
+const PLOTS: [(&str, &[Metric]); 13] = [
+    ("CPU", &[Metric::CpuUsage, Metric::PsiCpu]),
+    ("RAM", &[Metric::RamUsed]),
+    ("Memory Pressure", &[Metric::PsiMemory]),
+    ("VRAM", &[Metric::VramUsed]),
+    ("GPU Utilization", &[Metric::GpuUtil]),
+    ("PCIe", &[Metric::PcieTx, Metric::PcieRx]),
+    ("PCIe Link Utilization", &[Metric::PcieLinkUtil]),
+    ("Disk", &[Metric::DiskRead, Metric::DiskWrite]),
+    ("Disk Busy & I/O Pressure", &[Metric::DiskBusy, Metric::PsiIo]),
+    ("Network", &[Metric::NetRx, Metric::NetTx]),
+    ("Temperature", &[Metric::TempMax]),
+    ("Power", &[Metric::PowerPackage, Metric::PowerCore, Metric::PowerUncore, Metric::PowerDram]),
+    ("Efficiency", &[Metric::GbPerJoule]),
+];
+
+const PLOT_HEIGHT: f32 = 110.0;
+
+// Rolling line plots over the whole history window. The x axis is seconds
+// relative to the newest sample, so the right edge is always "now". Metrics
+// whose source is unavailable according to `latest` are left out.
+pub fn history_plots(ui: &mut egui::Ui, history: &History, latest: &HardwareMetrics, units: BandwidthUnit) {
+    let Some(now) = history.latest_time() else {
+        ui.weak("Collecting samples...");
+        return;
+    };
+    let window = history.window().as_secs_f64();
+
+    for (title, metrics) in PLOTS {
+        let metrics: Vec<Metric> = metrics
+            .iter()
+            .copied()
+            .filter(|m| latest.sources.is_empty() || latest.has_source(m.source_id()))
+            .collect();
+        if metrics.is_empty() {
+            continue;
+        }
+        // Bandwidths are stored in MiB/s and shown in the configured unit.
+        let bandwidth = metrics[0].is_bandwidth();
+        let unit = if bandwidth { units.label() } else { metrics[0].unit() };
+        let scale = if bandwidth { units.convert(1024.0 * 1024.0) } else { 1.0 };
+        ui.label(format!("{} ({})", title, unit));
+        Plot::new(format!("history_{}", title))
+            .height(PLOT_HEIGHT)
+            .include_x(-window)
+            .include_x(0.0)
+            .include_y(0.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .legend(Legend::default())
+            .x_axis_formatter(|mark, _range| format!("{:.0}s", mark.value))
+            .label_formatter(move |name, point| {
+                if name.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}\n{:.2} {}\n{:.1}s ago", name, point.y, unit, -point.x)
+                }
+            })
+            .show(ui, |plot_ui| {
+                for &metric in &metrics {
+                    let points: PlotPoints = history.series(metric).map(|[t, v]| [t - now, v * scale]).collect();
+                    plot_ui.line(Line::new(points).name(metric.label()));
+                }
+            });
+    }
+}
@@ -0,0 +1,42 @@
+use eframe::egui;
+
+use crate::metrics::HardwareMetrics;
+
+// Domain kinds in display order; psys is the whole SoC on recent laptops.
+const KINDS: [&str; 5] = ["package", "core", "uncore", "dram", "psys"];
+
+// One weak line with RAPL power per domain kind, summed over sockets, and
+// the data moved per joule. The hover text has every zone.
+pub fn power_summary(ui: &mut egui::Ui, m: &HardwareMetrics) {
+    if m.power.is_empty() {
+        return;
+    }
+    let mut parts: Vec<String> = KINDS
+        .into_iter()
+        .filter(|kind| m.power.iter().any(|d| d.kind() == *kind))
+        .map(|kind| format!("{} {:.1} W", kind, m.power_watts(kind)))
+        .collect();
+    if let Some(bytes) = m.bytes_per_joule() {
+        parts.push(format!("{:.3} GB/J", bytes / 1024.0 / 1024.0 / 1024.0));
+    }
+    ui.weak(format!("Power: {}", parts.join("  "))).on_hover_ui(|ui| {
+        egui::Grid::new("power_zones").striped(true).show(ui, |ui| {
+            for title in ["Zone", "Domain", "Power", "Energy"] {
+                ui.strong(title);
+            }
+            ui.end_row();
+            for domain in &m.power {
+                ui.label(&domain.zone);
+                ui.label(if domain.name == domain.package {
+                    domain.name.clone()
+                } else {
+                    format!("{} / {}", domain.package, domain.name)
+                });
+                ui.label(format!("{:.1} W", domain.watts));
+                ui.label(format!("{:.1} kJ", domain.energy_joules / 1e3));
+                ui.end_row();
+            }
+        });
+        ui.weak("GB/J: disk, PCIe and network traffic per joule of package and DRAM energy.");
+    });
+}
@@ -0,0 +1,44 @@
+use eframe::egui;
+
+use crate::metrics::{Pressure, Psi};
+
+fn resources(psi: &Psi) -> [(&'static str, Option<&Pressure>); 3] {
+    [("cpu", psi.cpu.as_ref()), ("mem", psi.memory.as_ref()), ("io", psi.io.as_ref())]
+}
+
+// A grid of some/full at every averaging window plus total stall time, for
+// hover text.
+pub fn pressure_details(ui: &mut egui::Ui, psi: &Psi) {
+    egui::Grid::new(ui.next_auto_id()).striped(true).show(ui, |ui| {
+        for title in ["", "", "avg10", "avg60", "avg300", "stalled"] {
+            ui.strong(title);
+        }
+        ui.end_row();
+        for (name, pressure) in resources(psi) {
+            let Some(pressure) = pressure else { continue };
+            for (kind, line) in [("some", Some(pressure.some)), ("full", pressure.full)] {
+                let Some(line) = line else { continue };
+                ui.label(name);
+                ui.label(kind);
+                ui.label(format!("{:.2}%", line.avg10));
+                ui.label(format!("{:.2}%", line.avg60));
+                ui.label(format!("{:.2}%", line.avg300));
+                ui.label(format!("{:.1} s", line.total_usec as f64 / 1e6));
+                ui.end_row();
+            }
+        }
+    });
+}
+
+// One weak line with the 10 s "some" pressure per resource; the rest is in
+// the hover text.
+pub fn pressure_summary(ui: &mut egui::Ui, psi: &Psi) {
+    if psi.is_empty() {
+        return;
+    }
+    let parts: Vec<String> = resources(psi)
+        .into_iter()
+        .filter_map(|(name, pressure)| Some(format!("{} {:.1}%", name, pressure?.some.avg10)))
+        .collect();
+    ui.weak(format!("stalled (PSI avg10): {}", parts.join("  "))).on_hover_ui(|ui| pressure_details(ui, psi));
+}
@@ -0,0 +1,139 @@
+use eframe::egui;
+use std::cmp::Ordering;
+
+use crate::config::BandwidthUnit;
+use crate::metrics::ProcessMetrics;
+
+// A full process list is hundreds of rows; past this many, the grid only
+// costs frame time.
+const MAX_ROWS: usize = 50;
+const TABLE_HEIGHT: f32 = 320.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProcessColumn {
+    Pid,
+    Name,
+    User,
+    Cpu,
+    Rss,
+    Read,
+    Write,
+}
+
+impl ProcessColumn {
+    const ALL: [ProcessColumn; 7] = [
+        ProcessColumn::Pid,
+        ProcessColumn::Name,
+        ProcessColumn::User,
+        ProcessColumn::Cpu,
+        ProcessColumn::Rss,
+        ProcessColumn::Read,
+        ProcessColumn::Write,
+    ];
+
+    fn title(self, units: BandwidthUnit) -> String {
+        match self {
+            ProcessColumn::Pid => "PID".to_string(),
+            ProcessColumn::Name => "Name".to_string(),
+            ProcessColumn::User => "User".to_string(),
+            ProcessColumn::Cpu => "CPU %".to_string(),
+            ProcessColumn::Rss => "RSS MB".to_string(),
+            ProcessColumn::Read => format!("Read {}", units.label()),
+            ProcessColumn::Write => format!("Write {}", units.label()),
+        }
+    }
+
+    fn compare(self, a: &ProcessMetrics, b: &ProcessMetrics) -> Ordering {
+        match self {
+            ProcessColumn::Pid => a.pid.cmp(&b.pid),
+            ProcessColumn::Name => a.name.cmp(&b.name),
+            ProcessColumn::User => a.user.cmp(&b.user),
+            ProcessColumn::Cpu => a.cpu_pct.partial_cmp(&b.cpu_pct).unwrap_or(Ordering::Equal),
+            ProcessColumn::Rss => a.rss_bytes.cmp(&b.rss_bytes),
+            // Unknown sorts below zero.
+            ProcessColumn::Read => a.read_bps.cmp(&b.read_bps),
+            ProcessColumn::Write => a.write_bps.cmp(&b.write_bps),
+        }
+    }
+}
+
+// Sort column and name/user filter of the process table. Lives in
+// MonitorApp so both survive repaints.
+pub struct ProcessView {
+    column: ProcessColumn,
+    descending: bool,
+    filter: String,
+}
+
+impl Default for ProcessView {
+    fn default() -> Self {
+        Self { column: ProcessColumn::Cpu, descending: true, filter: String::new() }
+    }
+}
+
+impl ProcessView {
+    // Same behaviour as the disk table: the active column flips direction,
+    // another column sorts biggest first unless it's text.
+    fn click(&mut self, column: ProcessColumn) {
+        if self.column == column {
+            self.descending = !self.descending;
+        } else {
+            self.column = column;
+            self.descending = !matches!(column, ProcessColumn::Name | ProcessColumn::User);
+        }
+    }
+
+    // Case-insensitive substring of the name or the user.
+    fn matches(&self, process: &ProcessMetrics) -> bool {
+        let filter = self.filter.trim().to_lowercase();
+        filter.is_empty()
+            || process.name.to_lowercase().contains(&filter)
+            || process.user.to_lowercase().contains(&filter)
+    }
+}
+
+pub fn process_table(ui: &mut egui::Ui, processes: &[ProcessMetrics], view: &mut ProcessView, units: BandwidthUnit) {
+    ui.horizontal(|ui| {
+        ui.label("Filter:");
+        ui.add(egui::TextEdit::singleline(&mut view.filter).hint_text("name or user").desired_width(160.0));
+    });
+
+    let mut rows: Vec<&ProcessMetrics> = processes.iter().filter(|p| view.matches(p)).collect();
+    rows.sort_by(|a, b| {
+        let ord = view.column.compare(a, b);
+        if view.descending { ord.reverse() } else { ord }
+    });
+    let hidden = rows.len().saturating_sub(MAX_ROWS);
+    rows.truncate(MAX_ROWS);
+
+    // The central panel doesn't scroll, so the table gets its own area.
+    egui::ScrollArea::vertical().id_salt("process_scroll").max_height(TABLE_HEIGHT).show(ui, |ui| {
+        egui::Grid::new("process_table").striped(true).num_columns(ProcessColumn::ALL.len()).show(ui, |ui| {
+            for column in ProcessColumn::ALL {
+                let mut title = column.title(units);
+                if view.column == column {
+                    title.push_str(if view.descending { " ⬇" } else { " ⬆" });
+                }
+                if ui.selectable_label(view.column == column, title).clicked() {
+                    view.click(column);
+                }
+            }
+            ui.end_row();
+
+            let rate = |bps: Option<u64>| bps.map_or("-".to_string(), |bps| format!("{:.2}", units.convert(bps as f64)));
+            for process in rows {
+                ui.label(process.pid.to_string());
+                ui.label(&process.name);
+                ui.label(&process.user);
+                ui.label(format!("{:.1}", process.cpu_pct));
+                ui.label(format!("{:.0}", process.rss_bytes as f64 / 1024.0 / 1024.0));
+                ui.label(rate(process.read_bps));
+                ui.label(rate(process.write_bps));
+                ui.end_row();
+            }
+        });
+    });
+    if hidden > 0 {
+        ui.weak(format!("{} more not shown", hidden));
+    }
+}
@@ -0,0 +1,41 @@
+use eframe::egui;
+
+use crate::replay::{Player, SPEEDS};
+
+fn format_position(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+// Transport bar shown above everything else while replaying a recording.
+pub fn replay_controls(ui: &mut egui::Ui, player: &mut Player) {
+    ui.horizontal(|ui| {
+        ui.label("Replay");
+        let label = if player.playing { "⏸ Pause" } else { "▶ Play" };
+        if ui.button(label).clicked() {
+            // Restart from the beginning when play is pressed at the end.
+            if !player.playing && player.position() >= player.duration() {
+                player.seek(0.0);
+            }
+            player.playing = !player.playing;
+        }
+
+        egui::ComboBox::from_id_salt("replay_speed")
+            .selected_text(format!("{}x", player.speed))
+            .width(60.0)
+            .show_ui(ui, |ui| {
+                for speed in SPEEDS {
+                    ui.selectable_value(&mut player.speed, speed, format!("{}x", speed));
+                }
+            });
+
+        let mut position = player.position();
+        let duration = player.duration();
+        ui.spacing_mut().slider_width = (ui.available_width() - 160.0).max(100.0);
+        let slider = egui::Slider::new(&mut position, 0.0..=duration.max(f64::EPSILON)).show_value(false);
+        if ui.add(slider).changed() {
+            player.seek(position);
+        }
+        ui.label(format!("{} / {}", format_position(position), format_position(duration)));
+    });
+}
@@ -0,0 +1,52 @@
+use eframe::egui;
+
+use crate::metrics::{SensorChip, SensorKind, SensorReading};
+
+const WARNING: egui::Color32 = egui::Color32::from_rgb(230, 140, 40);
+const CRITICAL: egui::Color32 = egui::Color32::from_rgb(220, 60, 60);
+
+fn format_value(kind: SensorKind, value: f64) -> String {
+    match kind {
+        SensorKind::Temperature => format!("{:.1} {}", value, kind.unit()),
+        SensorKind::Fan => format!("{:.0} {}", value, kind.unit()),
+        SensorKind::Voltage => format!("{:.3} {}", value, kind.unit()),
+        SensorKind::Power => format!("{:.1} {}", value, kind.unit()),
+    }
+}
+
+fn reading_row(ui: &mut egui::Ui, sensor: &SensorReading) {
+    ui.label(&sensor.label);
+    let mut value = egui::RichText::new(format_value(sensor.kind, sensor.value));
+    if sensor.is_critical() {
+        value = value.color(CRITICAL).strong();
+    } else if sensor.is_over_max() {
+        value = value.color(WARNING);
+    }
+    ui.label(value);
+    let threshold = |t: Option<f64>| t.map_or("-".to_string(), |t| format_value(sensor.kind, t));
+    ui.label(threshold(sensor.max));
+    ui.label(threshold(sensor.crit));
+    ui.end_row();
+}
+
+// One collapsible section per chip, named after its driver and device
+// ("nvme (nvme0)"), with a row per sensor.
+pub fn sensor_panel(ui: &mut egui::Ui, chips: &[SensorChip]) {
+    for (i, chip) in chips.iter().enumerate() {
+        let title = if chip.device.is_empty() { chip.name.clone() } else { format!("{} ({})", chip.name, chip.device) };
+        // A chip with something past its critical threshold starts open.
+        let alarm = chip.sensors.iter().any(SensorReading::is_critical);
+        egui::CollapsingHeader::new(title).id_salt(("sensor_chip", i)).default_open(alarm).show(ui, |ui| {
+            egui::Grid::new(("sensor_grid", i)).striped(true).num_columns(4).show(ui, |ui| {
+                ui.strong("Sensor");
+                ui.strong("Value");
+                ui.strong("Max");
+                ui.strong("Crit");
+                ui.end_row();
+                for sensor in &chip.sensors {
+                    reading_row(ui, sensor);
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use eframe::egui;
+
+use crate::topology::{NodeKind, PciNode};
+
+const HIGHLIGHT: egui::Color32 = egui::Color32::from_rgb(90, 170, 250);
+
+fn node_label(node: &PciNode, tag: Option<&String>) -> String {
+    let mut label = format!("{}  ", node.address);
+    if let Some(vendor) = node.vendor_name() {
+        label += &format!("{} ", vendor);
+    }
+    label += node.class_name();
+    if node.kind == NodeKind::SwitchPort {
+        label += " (switch port)";
+    }
+    if let Some(link) = &node.link {
+        label += &format!("  Gen{} x{}", link.gen, link.width);
+        if link.slower_than_max() || link.narrower_than_max() {
+            label += &format!(" (max Gen{} x{})", link.max_gen, link.max_width);
+        }
+    }
+    if let Some(numa) = node.numa_node {
+        label += &format!("  NUMA {}", numa);
+    }
+    if let Some(tag) = tag {
+        label += &format!("  [{}]", tag);
+    }
+    label
+}
+
+fn node_row(ui: &mut egui::Ui, node: &PciNode, highlights: &HashMap<String, String>) {
+    let tag = highlights.get(&node.address);
+    let mut text = egui::RichText::new(node_label(node, tag)).monospace();
+    if tag.is_some() {
+        text = text.color(HIGHLIGHT).strong();
+    }
+    let hover = format!("vendor {:04x}  device {:04x}  class {:06x}", node.vendor, node.device, node.class);
+    if node.children.is_empty() {
+        ui.label(text).on_hover_text(hover);
+        return;
+    }
+    // Branches leading to a monitored device start open, everything else
+    // (chipset bridges, USB, audio) starts folded away.
+    egui::CollapsingHeader::new(text)
+        .id_salt(&node.address)
+        .default_open(node.contains_any(highlights))
+        .show(ui, |ui| {
+            for child in &node.children {
+                node_row(ui, child, highlights);
+            }
+        })
+        .header_response
+        .on_hover_text(hover);
+}
+
+// The PCI tree below the host bridges. `highlights` maps sysfs addresses of
+// monitored devices to the tag shown next to them, e.g. "GPU 0".
+pub fn topology_tree(ui: &mut egui::Ui, roots: &[PciNode], highlights: &HashMap<String, String>) {
+    for root in roots {
+        node_row(ui, root, highlights);
+    }
+}